@@ -0,0 +1,258 @@
+use rand::Rng;
+
+use crate::game::{
+    card::Card,
+    card_stats::{CardCounters, CardStats},
+    cost_model::WeightedCostModel,
+    deck::{Deck, DeckTrait},
+    setlist::SetList,
+    Game,
+};
+
+use super::regression::weighted_least_squares;
+
+const FEATURES: [&str; 8] = [
+    "Base",
+    "Attack",
+    "Defense",
+    "Taunt",
+    "Distortion",
+    "Trample",
+    "FirstStrike",
+    "Effect",
+];
+//Below this difference (in mana) a fitted weight is considered in line with the current one
+const WEIGHT_TOLERANCE: f64 = 0.25;
+const WIN_RATE_TOLERANCE: f64 = 0.01;
+
+pub struct RangeReport {
+    pub label: String,
+    pub plays: u32,
+    pub excess_win_rate: f64,
+}
+
+pub struct CostFit {
+    pub current: WeightedCostModel,
+    pub fitted: WeightedCostModel,
+    //Change in win rate when played for one extra mana
+    pub mana_effect: f64,
+    pub attack_ranges: Vec<RangeReport>,
+    pub defense_ranges: Vec<RangeReport>,
+}
+
+//Shifts every cost by -1, 0 or +1 so that cost is not collinear with the card's stats
+pub fn jitter_costs<R: Rng + ?Sized>(setlist: &SetList, rng: &mut R) -> SetList {
+    setlist
+        .iter()
+        .map(|card| {
+            let cost = card.get_cost() as i32 + rng.gen_range(-1..=1);
+            card.with_cost(cost.max(1) as u32)
+        })
+        .collect()
+}
+
+//Plays n games between random decks and collects the stats of every card of the setlist
+pub fn measure_played_win_rates(game: &mut Game, n_games: usize) -> CardStats {
+    let mut card_stats = CardStats::default();
+    game.track_card_stats = true;
+
+    for _ in 0..n_games {
+        game.p1.base_deck = Deck::new(&game.setlist, &mut game.rng);
+        game.p2.base_deck = Deck::new(&game.setlist, &mut game.rng);
+
+        let stats = game.play();
+        for player_stats in [stats.p1_card_stats, stats.p2_card_stats].iter().flatten() {
+            card_stats.merge(player_stats);
+        }
+    }
+
+    game.track_card_stats = false;
+    card_stats
+}
+
+//None when no card of the setlist was played
+pub fn fit_costs(card_stats: &CardStats, current: &WeightedCostModel) -> Option<CostFit> {
+    let mut rows = Vec::with_capacity(card_stats.len());
+    let mut win_rates = Vec::with_capacity(card_stats.len());
+    let mut weights = Vec::with_capacity(card_stats.len());
+
+    for (card, counters) in card_stats
+        .iter()
+        .filter(|(_, counters)| counters.games_played > 0)
+    {
+        let mut row = features(card, current.stat_exponent).to_vec();
+        row.push(card.get_cost() as f64);
+        rows.push(row);
+        win_rates.push(counters.win_rate_when_played());
+        weights.push(counters.games_played as f64);
+    }
+
+    let coefficients = weighted_least_squares(&rows, &win_rates, &weights, 1e-3)?;
+    let mana_effect = coefficients[FEATURES.len()];
+
+    let total_plays: f64 = weights.iter().sum();
+    let mean_win_rate = win_rates
+        .iter()
+        .zip(&weights)
+        .map(|(win_rate, weight)| win_rate * weight)
+        .sum::<f64>()
+        / total_plays;
+
+    //Balanced cards are the ones whose expected win rate is the mean one:
+    //cost = (b0 - mean + sum(b_i * x_i)) / -b_cost
+    let current_weights = weights_of(current);
+    let mut fitted_weights = current_weights;
+    for i in 0..FEATURES.len() {
+        let has_data = rows.iter().any(|row| row[i] != 0.0);
+        if mana_effect < 0.0 && has_data {
+            let coefficient = if i == 0 {
+                coefficients[0] - mean_win_rate
+            } else {
+                coefficients[i]
+            };
+            fitted_weights[i] = coefficient / -mana_effect;
+        }
+    }
+
+    let mut fitted = *current;
+    set_weights(&mut fitted, fitted_weights);
+
+    Some(CostFit {
+        current: *current,
+        fitted,
+        mana_effect,
+        attack_ranges: range_reports(card_stats, mean_win_rate, |card| {
+            let low = card.get_attack() / 4 * 4;
+            format!("Attack {}-{}", low, low + 3)
+        }),
+        defense_ranges: range_reports(card_stats, mean_win_rate, |card| match card.get_defense() {
+            0 => "Defense 0".to_owned(),
+            defense => {
+                let low = (defense - 1) / 4 * 4 + 1;
+                format!("Defense {}-{}", low, low + 3)
+            }
+        }),
+    })
+}
+
+pub fn print_report(fit: &CostFit) {
+    println!("Win rate change per mana: {:+.4}", fit.mana_effect);
+    if fit.mana_effect >= 0.0 {
+        println!("Cost has no negative effect on win rate, keyword weights were not fitted");
+    }
+
+    println!(
+        "{:<12} {:>8} {:>8}  Verdict",
+        "Keyword", "Current", "Fitted"
+    );
+    let current = weights_of(&fit.current);
+    let fitted = weights_of(&fit.fitted);
+    for i in 0..FEATURES.len() {
+        let verdict = if i == 0 {
+            ""
+        } else if fitted[i] - current[i] > WEIGHT_TOLERANCE {
+            "under-costed"
+        } else if current[i] - fitted[i] > WEIGHT_TOLERANCE {
+            "over-costed"
+        } else {
+            "balanced"
+        };
+        println!(
+            "{:<12} {:>8.3} {:>8.3}  {}",
+            FEATURES[i], current[i], fitted[i], verdict
+        );
+    }
+
+    println!(
+        "{:<16} {:>8} {:>10}  Verdict",
+        "Stat range", "Plays", "Excess WR"
+    );
+    for range in fit.attack_ranges.iter().chain(&fit.defense_ranges) {
+        let verdict = if range.excess_win_rate > WIN_RATE_TOLERANCE {
+            "under-costed"
+        } else if range.excess_win_rate < -WIN_RATE_TOLERANCE {
+            "over-costed"
+        } else {
+            "balanced"
+        };
+        println!(
+            "{:<16} {:>8} {:>+10.4}  {}",
+            range.label, range.plays, range.excess_win_rate, verdict
+        );
+    }
+}
+
+fn features(card: &Card, stat_exponent: f32) -> [f64; 8] {
+    let effects_cost = card.get_on_play().map_or(0.0, |effect| effect.cost())
+        + card.get_on_death().map_or(0.0, |effect| effect.cost());
+
+    [
+        1.0,
+        (card.get_attack() as f64).powf(stat_exponent as f64),
+        (card.get_defense() as f64).powf(stat_exponent as f64),
+        card.get_taunt() as u32 as f64,
+        card.get_distortion() as u32 as f64,
+        card.get_trample() as u32 as f64,
+        card.get_first_strike() as u32 as f64,
+        effects_cost as f64,
+    ]
+}
+
+fn weights_of(model: &WeightedCostModel) -> [f64; 8] {
+    [
+        model.base,
+        model.attack,
+        model.defense,
+        model.taunt,
+        model.distortion,
+        model.trample,
+        model.first_strike,
+        model.effect,
+    ]
+    .map(|weight| weight as f64)
+}
+
+fn set_weights(model: &mut WeightedCostModel, weights: [f64; 8]) {
+    let weights = weights.map(|weight| weight as f32);
+    model.base = weights[0];
+    model.attack = weights[1];
+    model.defense = weights[2];
+    model.taunt = weights[3];
+    model.distortion = weights[4];
+    model.trample = weights[5];
+    model.first_strike = weights[6];
+    model.effect = weights[7];
+}
+
+fn range_reports(
+    card_stats: &CardStats,
+    mean_win_rate: f64,
+    label: impl Fn(&Card) -> String,
+) -> Vec<RangeReport> {
+    let mut ranges: Vec<(String, CardCounters)> = vec![];
+    for (card, counters) in card_stats.iter() {
+        let label = label(card);
+        match ranges.iter_mut().find(|(range, _)| *range == label) {
+            Some((_, total)) => total.merge(counters),
+            None => ranges.push((label, *counters)),
+        }
+    }
+    ranges.sort_by_key(|(label, _)| natural_order(label));
+
+    ranges
+        .into_iter()
+        .filter(|(_, counters)| counters.games_played > 0)
+        .map(|(label, counters)| RangeReport {
+            label,
+            plays: counters.games_played,
+            excess_win_rate: counters.win_rate_when_played() - mean_win_rate,
+        })
+        .collect()
+}
+
+//Sorts "Attack 4-7" before "Attack 12-15"
+fn natural_order(label: &str) -> (String, u32) {
+    let (name, range) = label.rsplit_once(' ').unwrap_or((label, ""));
+    let low = range.split('-').next().unwrap_or("").parse().unwrap_or(0);
+    (name.to_owned(), low)
+}
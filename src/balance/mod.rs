@@ -0,0 +1,6 @@
+pub mod cost_fit;
+pub mod nash;
+pub mod ranking;
+pub mod rating;
+pub mod regression;
+pub mod tournament;
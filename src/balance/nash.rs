@@ -0,0 +1,143 @@
+use std::{fs::File, io::Write};
+
+use super::tournament::MatchupMatrix;
+
+pub struct Equilibrium {
+    //Probability of each deck in the Nash mixture
+    pub mixture: Vec<f64>,
+    //Win rate of each deck against the mixture, at most 0.5 at the equilibrium
+    pub win_rate_vs_mixture: Vec<f64>,
+    //Win rate of the best response to each deck minus 0.5
+    pub exploitability: Vec<f64>,
+    pub best_response: Vec<usize>,
+    //Exploitability of the mixture itself, 0 up to the precision of the solver
+    pub mixture_exploitability: f64,
+}
+
+impl Equilibrium {
+    //1 / sum of the squared probabilities: 1 when a single deck dominates the metagame
+    pub fn effective_decks(&self) -> f64 {
+        1.0 / self.mixture.iter().map(|p| p * p).sum::<f64>()
+    }
+}
+
+//The metagame is the symmetric zero-sum game whose payoff is the matchup matrix. The mixture y
+//minimises the best win rate against it: max sum(w) subject to M w <= 1, w >= 0, y = w / sum(w).
+//Win rates are shifted by 1 so that every payoff is positive.
+pub fn solve(matrix: &MatchupMatrix) -> Equilibrium {
+    let n = matrix.len();
+    let payoffs: Vec<Vec<f64>> = matrix
+        .win_rates
+        .iter()
+        .map(|row| row.iter().map(|rate| rate + 1.0).collect())
+        .collect();
+
+    let weights = simplex(&payoffs);
+    let total: f64 = weights.iter().sum();
+    let mixture: Vec<f64> = weights.iter().map(|w| w / total).collect();
+
+    let win_rate_vs_mixture: Vec<f64> = matrix
+        .win_rates
+        .iter()
+        .map(|row| row.iter().zip(&mixture).map(|(rate, p)| rate * p).sum())
+        .collect();
+
+    let mut exploitability = vec![0.0; n];
+    let mut best_response = vec![0; n];
+    for deck in 0..n {
+        let (best, win_rate) = (0..n)
+            .map(|opponent| (opponent, matrix.win_rates[opponent][deck]))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap();
+        exploitability[deck] = win_rate - 0.5;
+        best_response[deck] = best;
+    }
+
+    Equilibrium {
+        mixture_exploitability: win_rate_vs_mixture
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max)
+            - 0.5,
+        mixture,
+        win_rate_vs_mixture,
+        exploitability,
+        best_response,
+    }
+}
+
+//max sum(x) subject to a x <= 1, x >= 0, with Bland's rule so that degenerate pivots cannot cycle
+fn simplex(a: &[Vec<f64>]) -> Vec<f64> {
+    let n = a.len();
+    let width = 2 * n + 1;
+    //Constraint rows [a | identity | 1] and the objective row last
+    let mut tableau: Vec<Vec<f64>> = a
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut line = vec![0.0; width];
+            line[..n].copy_from_slice(row);
+            line[n + i] = 1.0;
+            line[2 * n] = 1.0;
+            line
+        })
+        .collect();
+    let mut objective = vec![0.0; width];
+    objective[..n].fill(-1.0);
+    tableau.push(objective);
+    let mut basis: Vec<usize> = (n..2 * n).collect();
+
+    while let Some(column) = (0..2 * n).find(|&column| tableau[n][column] < -1e-12) {
+        let pivot = (0..n)
+            .filter(|&row| tableau[row][column] > 1e-12)
+            .min_by(|&i, &j| {
+                let ratio_i = tableau[i][2 * n] / tableau[i][column];
+                let ratio_j = tableau[j][2 * n] / tableau[j][column];
+                ratio_i.total_cmp(&ratio_j).then(basis[i].cmp(&basis[j]))
+            })
+            .expect("The matchup game is bounded since every payoff is positive");
+
+        let factor = tableau[pivot][column];
+        for value in tableau[pivot].iter_mut() {
+            *value /= factor;
+        }
+        let pivot_row = tableau[pivot].clone();
+        for (row, line) in tableau.iter_mut().enumerate() {
+            if row == pivot {
+                continue;
+            }
+            let factor = line[column];
+            for (value, pivot_value) in line.iter_mut().zip(&pivot_row) {
+                *value -= factor * pivot_value;
+            }
+        }
+        basis[pivot] = column;
+    }
+
+    let mut x = vec![0.0; n];
+    for (row, variable) in basis.iter().enumerate() {
+        if *variable < n {
+            x[*variable] = tableau[row][2 * n];
+        }
+    }
+    x
+}
+
+pub fn export_equilibrium(matrix: &MatchupMatrix, equilibrium: &Equilibrium, file: &mut File) {
+    file.write_all(b"Deck,Probability,WinRateVsMixture,Exploitability,BestResponse\n")
+        .unwrap();
+    for deck in 0..matrix.len() {
+        file.write_all(
+            format!(
+                "{},{:.4},{:.4},{:.4},{}\n",
+                matrix.names[deck],
+                equilibrium.mixture[deck],
+                equilibrium.win_rate_vs_mixture[deck],
+                equilibrium.exploitability[deck],
+                matrix.names[equilibrium.best_response[deck]]
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+use std::{fs::File, io::Write};
+
+use crate::{
+    game::{
+        card::Card,
+        deck::{Deck, DeckTrait, MAX_COPIES},
+        naming::CardNames,
+        setlist::SetList,
+        Game,
+    },
+    parallel::parallel_map,
+};
+
+pub struct CardRank {
+    pub card: Card,
+    pub win_rate_delta: f64,
+    //Delta compared to the average delta of the cards of the same cost
+    pub delta_vs_cost: f64,
+}
+
+struct Baseline {
+    deck: Deck,
+    opponent: Deck,
+    slot: usize,
+    win_rate: f64,
+}
+
+//Inserts every card of the setlist in random baseline decks and measures the win rate delta. Each
+//card has its own game seeded from the seed and the card, the results do not depend on the number
+//of threads.
+pub fn rank_cards(
+    setlist: &SetList,
+    n_baselines: usize,
+    n_games: usize,
+    seed: u64,
+) -> Vec<CardRank> {
+    let mut game = Game::seeded(setlist.clone(), seed);
+    let baselines: Vec<Baseline> = (0..n_baselines)
+        .map(|i| {
+            game.p1.base_deck = Deck::new(setlist, &mut game.rng);
+            game.p2.base_deck = Deck::new(setlist, &mut game.rng);
+            Baseline {
+                deck: game.p1.base_deck,
+                opponent: game.p2.base_deck,
+                slot: i % 30,
+                //Baselines are measured more precisely as every delta depends on them
+                win_rate: game.win_rate(n_games * 4),
+            }
+        })
+        .collect();
+
+    let deltas = parallel_map(setlist, |index, card| {
+        //The baselines were measured on the seed itself
+        let mut game = Game::seeded(setlist.clone(), seed ^ (index as u64 + 1));
+        average_delta(&mut game, &baselines, card, n_games)
+    });
+
+    let mut ranks: Vec<CardRank> = setlist
+        .iter()
+        .zip(deltas)
+        .map(|(card, win_rate_delta)| CardRank {
+            card: *card,
+            win_rate_delta,
+            delta_vs_cost: 0.0,
+        })
+        .collect();
+
+    let max_cost = ranks
+        .iter()
+        .map(|rank| rank.card.get_cost())
+        .max()
+        .unwrap_or(0);
+    for cost in 0..=max_cost {
+        let same_cost: Vec<f64> = ranks
+            .iter()
+            .filter(|rank| rank.card.get_cost() == cost)
+            .map(|rank| rank.win_rate_delta)
+            .collect();
+        if same_cost.is_empty() {
+            continue;
+        }
+        let average = same_cost.iter().sum::<f64>() / same_cost.len() as f64;
+        for rank in ranks.iter_mut().filter(|rank| rank.card.get_cost() == cost) {
+            rank.delta_vs_cost = rank.win_rate_delta - average;
+        }
+    }
+
+    ranks.sort_by(|a, b| b.win_rate_delta.total_cmp(&a.win_rate_delta));
+    ranks
+}
+
+//A baseline already holding MAX_COPIES of the card outside its slot has no slot that can take
+//another copy, it is left out of the card's average
+fn average_delta(game: &mut Game, baselines: &[Baseline], card: &Card, n_games: usize) -> f64 {
+    let mut total_delta = 0.0;
+    let mut n_deltas = 0;
+    for baseline in baselines {
+        let copies = baseline
+            .deck
+            .iter()
+            .enumerate()
+            .filter(|(slot, other)| *slot != baseline.slot && *other == card)
+            .count();
+        if copies >= MAX_COPIES {
+            continue;
+        }
+
+        game.p1.base_deck = baseline.deck;
+        game.p1.base_deck[baseline.slot] = *card;
+        game.p2.base_deck = baseline.opponent;
+        total_delta += game.win_rate(n_games) - baseline.win_rate;
+        n_deltas += 1;
+    }
+
+    if n_deltas == 0 {
+        return 0.0;
+    }
+    total_delta / n_deltas as f64
+}
+
+pub fn export_ranking(ranks: &[CardRank], names: &CardNames, file: &mut File) {
+    file.write_all(
+        b"Rank,Id,Name,Cost,Attack,Defense,Taunt,Distortion,Trample,FirstStrike,\
+WinRateDelta,DeltaVsCost\n",
+    )
+    .unwrap();
+
+    for (i, rank) in ranks.iter().enumerate() {
+        let card = &rank.card;
+        file.write_all(
+            format!(
+                "{},{},{},{},{},{},{},{},{},{},{:.4},{:.4}\n",
+                i + 1,
+                card.get_id(),
+                names.label(card),
+                card.get_cost(),
+                card.get_attack(),
+                card.get_defense(),
+                card.get_taunt() as u32,
+                card.get_distortion() as u32,
+                card.get_trample() as u32,
+                card.get_first_strike() as u32,
+                rank.win_rate_delta,
+                rank.delta_vs_cost,
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,88 @@
+use std::{f64::consts::LN_10, fs::File, io::Write};
+
+use super::{regression::solve, tournament::MatchupMatrix};
+
+//Elo points of the prior standard deviation, it keeps the ratings of unbeaten decks finite
+const PRIOR_DEVIATION: f64 = 1000.0;
+const ELO_SCALE: f64 = 400.0 / LN_10;
+
+pub struct Rating {
+    pub name: String,
+    pub elo: f64,
+    //Standard error in Elo points, the 95% interval is elo ± 1.96 deviation
+    pub deviation: f64,
+}
+
+//Elo ratings fitted to every game of the matrix at once (Bradley-Terry model), the
+//deviations come from the curvature of the likelihood. Ratings average 1500.
+pub fn elo_ratings(matrix: &MatchupMatrix) -> Vec<Rating> {
+    let n = matrix.len();
+    let prior = (ELO_SCALE / PRIOR_DEVIATION).powi(2);
+    let mut strengths: Vec<f64> = vec![0.0; n];
+    let mut information = vec![vec![0.0; n]; n];
+
+    //Newton's method on the log posterior
+    for _ in 0..100 {
+        let mut gradient: Vec<f64> = strengths.iter().map(|s| -s * prior).collect();
+        information = vec![vec![0.0; n]; n];
+        for (i, line) in information.iter_mut().enumerate() {
+            line[i] = prior;
+        }
+
+        for i in 0..n {
+            for j in 0..n {
+                if i == j || matrix.games[i][j] == 0 {
+                    continue;
+                }
+                let expected = 1.0 / (1.0 + (strengths[j] - strengths[i]).exp());
+                let games = matrix.games[i][j] as f64;
+                gradient[i] += matrix.wins(i, j) - games * expected;
+                information[i][i] += games * expected * (1.0 - expected);
+                information[i][j] -= games * expected * (1.0 - expected);
+            }
+        }
+
+        let step = solve(information.clone(), gradient);
+        for (strength, delta) in strengths.iter_mut().zip(&step) {
+            *strength += delta;
+        }
+        if step.iter().all(|delta| delta.abs() < 1e-9) {
+            break;
+        }
+    }
+
+    //Variance of the strength minus the mean strength, the prior alone constrains the mean
+    let mean = strengths.iter().sum::<f64>() / n as f64;
+    (0..n)
+        .map(|i| {
+            let mut centered = vec![-1.0 / n as f64; n];
+            centered[i] += 1.0;
+            let covariance = solve(information.clone(), centered.clone());
+            let variance: f64 = centered.iter().zip(&covariance).map(|(c, v)| c * v).sum();
+            Rating {
+                name: matrix.names[i].clone(),
+                elo: 1500.0 + (strengths[i] - mean) * ELO_SCALE,
+                deviation: variance.sqrt() * ELO_SCALE,
+            }
+        })
+        .collect()
+}
+
+pub fn export_ratings(ratings: &[Rating], file: &mut File) {
+    file.write_all(b"Deck,Elo,Deviation,Low95,High95\n")
+        .unwrap();
+    for rating in ratings {
+        file.write_all(
+            format!(
+                "{},{:.1},{:.1},{:.1},{:.1}\n",
+                rating.name,
+                rating.elo,
+                rating.deviation,
+                rating.elo - 1.96 * rating.deviation,
+                rating.elo + 1.96 * rating.deviation
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+//Weighted ridge regression solved through the normal equations. Rows without weight are left out,
+//there is nothing to fit when none is left.
+pub fn weighted_least_squares(
+    rows: &[Vec<f64>],
+    y: &[f64],
+    weights: &[f64],
+    ridge: f64,
+) -> Option<Vec<f64>> {
+    let n_features = rows
+        .iter()
+        .zip(weights)
+        .find(|(_, weight)| **weight > 0.0)?
+        .0
+        .len();
+    let mut xtx = vec![vec![0.0; n_features]; n_features];
+    let mut xty = vec![0.0; n_features];
+
+    for ((row, target), weight) in rows
+        .iter()
+        .zip(y)
+        .zip(weights)
+        .filter(|(_, weight)| **weight > 0.0)
+    {
+        for i in 0..n_features {
+            xty[i] += weight * row[i] * target;
+            for j in 0..n_features {
+                xtx[i][j] += weight * row[i] * row[j];
+            }
+        }
+    }
+
+    for (i, line) in xtx.iter_mut().enumerate() {
+        line[i] += ridge;
+    }
+
+    Some(solve(xtx, xty))
+}
+
+//Gaussian elimination with partial pivoting
+pub fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Vec<f64> {
+    let n = b.len();
+
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+            .unwrap();
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        if a[col][col].abs() < f64::EPSILON {
+            continue;
+        }
+
+        for row in col + 1..n {
+            let factor = a[row][col] / a[col][col];
+            let pivot_row = a[col].clone();
+            for (value, pivot_value) in a[row].iter_mut().zip(pivot_row).skip(col) {
+                *value -= factor * pivot_value;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        if a[row][row].abs() < f64::EPSILON {
+            continue;
+        }
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+
+    x
+}
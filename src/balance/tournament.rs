@@ -0,0 +1,111 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+};
+
+use crate::{
+    game::{deck::Deck, setlist::SetList, Game},
+    optimiser::evaluation::evaluate,
+    parallel::parallel_map,
+};
+
+//Win rates of the row deck against the column deck, mirrors are not played and count as 0.5
+#[derive(Debug, Clone)]
+pub struct MatchupMatrix {
+    pub names: Vec<String>,
+    pub win_rates: Vec<Vec<f64>>,
+    //Games played by each pairing, 0 when the matrix is loaded from a file
+    pub games: Vec<Vec<u32>>,
+}
+
+impl MatchupMatrix {
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn wins(&self, row: usize, column: usize) -> f64 {
+        self.win_rates[row][column] * self.games[row][column] as f64
+    }
+
+    //Header of deck names, then one row per deck starting with its name
+    pub fn export_csv(&self, file: &mut File) {
+        file.write_all(format!(",{}\n", self.names.join(",")).as_bytes())
+            .unwrap();
+        for (name, row) in self.names.iter().zip(&self.win_rates) {
+            let row: Vec<String> = row.iter().map(|rate| format!("{rate:.4}")).collect();
+            file.write_all(format!("{name},{}\n", row.join(",")).as_bytes())
+                .unwrap();
+        }
+    }
+
+    pub fn load_csv(path: &str) -> MatchupMatrix {
+        let content = fs::read_to_string(path).unwrap();
+        let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+        let names: Vec<String> = lines
+            .next()
+            .unwrap_or("")
+            .split(',')
+            .skip(1)
+            .map(|name| name.trim().to_owned())
+            .collect();
+
+        let win_rates: Vec<Vec<f64>> = lines
+            .map(|line| {
+                line.split(',')
+                    .skip(1)
+                    .map(|rate| {
+                        rate.trim()
+                            .parse()
+                            .unwrap_or_else(|_| panic!("{path}: invalid win rate '{rate}'"))
+                    })
+                    .collect()
+            })
+            .collect();
+        if win_rates.len() != names.len() || win_rates.iter().any(|row| row.len() != names.len()) {
+            panic!("{path}: the matchup matrix is not square");
+        }
+
+        MatchupMatrix {
+            games: vec![vec![0; names.len()]; names.len()],
+            names,
+            win_rates,
+        }
+    }
+}
+
+//Plays every pairing n games on each side. Each pairing has its own game seeded from the seed and
+//the pairing, the results do not depend on the number of threads.
+pub fn round_robin(
+    setlist: &SetList,
+    decks: &[(String, Deck)],
+    n_games: u32,
+    seed: u64,
+) -> MatchupMatrix {
+    let pairings: Vec<(usize, usize)> = (0..decks.len())
+        .flat_map(|i| (i + 1..decks.len()).map(move |j| (i, j)))
+        .collect();
+
+    let results = parallel_map(&pairings, |pairing, (i, j)| {
+        let mut game = Game::seeded(setlist.clone(), seed ^ pairing as u64);
+        game.p1.base_deck = decks[*i].1;
+        game.p2.base_deck = decks[*j].1;
+        let evaluation = evaluate(&mut game, n_games);
+        (evaluation.wins, evaluation.games)
+    });
+
+    let n = decks.len();
+    let mut win_rates = vec![vec![0.5; n]; n];
+    let mut games = vec![vec![0; n]; n];
+    for ((i, j), (wins, played)) in pairings.into_iter().zip(results) {
+        win_rates[i][j] = wins as f64 / played as f64;
+        win_rates[j][i] = 1.0 - win_rates[i][j];
+        games[i][j] = played;
+        games[j][i] = played;
+    }
+
+    MatchupMatrix {
+        names: decks.iter().map(|(name, _)| name.clone()).collect(),
+        win_rates,
+        games,
+    }
+}
@@ -0,0 +1,266 @@
+use std::{env, ops::RangeInclusive};
+
+use crate::{
+    game::{card::Keyword, card_filter::CardFilter},
+    optimiser::{
+        map_elites::{Axis, Descriptor},
+        proposal::{CardProposal, SlotProposal},
+        search::{Restart, SearchConfig},
+        stopping::Stopping,
+    },
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    #[default]
+    Optimise,
+    FitCosts,
+    CardStats,
+    RankCards,
+    ExportCards,
+    Gauntlet,
+    Tournament,
+    Nash,
+    MapElites,
+    Upgrades,
+    DiffDecks,
+    ExportRun,
+}
+
+#[derive(Debug, Default)]
+pub struct Args {
+    pub command: Command,
+    pub cost_model: Option<String>,
+    pub cards: Option<String>,
+    pub generator: Option<String>,
+    pub locale: Option<String>,
+    pub names: Option<String>,
+    pub filter: CardFilter,
+    pub games: Option<usize>,
+    pub baselines: Option<usize>,
+    pub samples: Option<usize>,
+    pub output: Option<String>,
+    pub deck: Option<String>,
+    pub opponent: Option<String>,
+    pub gauntlet: Option<String>,
+    pub decks: Option<String>,
+    pub random_decks: Option<usize>,
+    pub matchups: Option<String>,
+    pub proposal: CardProposal,
+    pub slots: SlotProposal,
+    pub seed: Option<u64>,
+    pub iterations: Option<usize>,
+    pub rounds: Option<usize>,
+    pub checkpoint: Option<String>,
+    pub checkpoint_every: Option<usize>,
+    pub resume: Option<String>,
+    pub hall_of_fame: Option<String>,
+    pub champions: Option<usize>,
+    pub target_turn: Option<f32>,
+    pub target_curve: Option<Vec<f64>>,
+    pub axes: Vec<Axis>,
+    //Search options given, the others keep their default or the value saved in the checkpoint
+    pub tabu: Option<usize>,
+    pub restart: Option<Restart>,
+    pub restart_after: Option<usize>,
+    pub kick: Option<usize>,
+    pub steepest: Option<usize>,
+    pub stopping: Stopping,
+    pub run: Option<String>,
+}
+
+impl Args {
+    pub fn parse() -> Args {
+        let mut args = Args::default();
+        let mut argv = env::args().skip(1).peekable();
+
+        if let Some(command) = argv.next_if(|arg| !arg.starts_with("--")) {
+            args.command = match command.as_str() {
+                "optimise" => Command::Optimise,
+                "fit-costs" => Command::FitCosts,
+                "card-stats" => Command::CardStats,
+                "rank-cards" => Command::RankCards,
+                "export-cards" => Command::ExportCards,
+                "gauntlet" => Command::Gauntlet,
+                "tournament" => Command::Tournament,
+                "nash" => Command::Nash,
+                "map-elites" => Command::MapElites,
+                "upgrades" => Command::Upgrades,
+                "diff-decks" => Command::DiffDecks,
+                "export-run" => Command::ExportRun,
+                _ => panic!("Unknown command: {command}"),
+            };
+        }
+
+        while let Some(arg) = argv.next() {
+            match arg.as_str() {
+                "--cost-model" => args.cost_model = Some(expect_value(&arg, argv.next())),
+                "--cards" => args.cards = Some(expect_value(&arg, argv.next())),
+                "--generator" => args.generator = Some(expect_value(&arg, argv.next())),
+                "--locale" => args.locale = Some(expect_value(&arg, argv.next())),
+                "--names" => args.names = Some(expect_value(&arg, argv.next())),
+                "--cost" => args.filter.cost = Some(parse_range(&arg, argv.next())),
+                "--attack" => args.filter.attack = Some(parse_range(&arg, argv.next())),
+                "--defense" => args.filter.defense = Some(parse_range(&arg, argv.next())),
+                "--with" => args.filter.with = parse_keywords(&arg, argv.next()),
+                "--without" => args.filter.without = parse_keywords(&arg, argv.next()),
+                "--games" => args.games = Some(parse_count(&arg, argv.next())),
+                "--samples" => args.samples = Some(parse_value(&arg, argv.next())),
+                "--baselines" => args.baselines = Some(parse_count(&arg, argv.next())),
+                "--output" => args.output = Some(expect_value(&arg, argv.next())),
+                "--deck" => args.deck = Some(expect_value(&arg, argv.next())),
+                "--opponent" => args.opponent = Some(expect_value(&arg, argv.next())),
+                "--gauntlet" => args.gauntlet = Some(expect_value(&arg, argv.next())),
+                "--decks" => args.decks = Some(expect_value(&arg, argv.next())),
+                "--random-decks" => args.random_decks = Some(parse_value(&arg, argv.next())),
+                "--matchups" => args.matchups = Some(expect_value(&arg, argv.next())),
+                "--proposal" => {
+                    let name = expect_value(&arg, argv.next());
+                    args.proposal = CardProposal::from_name(&name)
+                        .unwrap_or_else(|| panic!("Unknown proposal for {arg}: {name}"));
+                }
+                "--slots" => {
+                    let name = expect_value(&arg, argv.next());
+                    args.slots = SlotProposal::from_name(&name)
+                        .unwrap_or_else(|| panic!("Unknown slot proposal for {arg}: {name}"));
+                }
+                "--seed" => args.seed = Some(parse_value(&arg, argv.next())),
+                "--iterations" => args.iterations = Some(parse_count(&arg, argv.next())),
+                "--rounds" => args.rounds = Some(parse_value(&arg, argv.next())),
+                "--checkpoint" => args.checkpoint = Some(expect_value(&arg, argv.next())),
+                "--checkpoint-every" => {
+                    args.checkpoint_every = Some(parse_count(&arg, argv.next()))
+                }
+                "--resume" => args.resume = Some(expect_value(&arg, argv.next())),
+                "--hall-of-fame" => args.hall_of_fame = Some(expect_value(&arg, argv.next())),
+                "--champions" => args.champions = Some(parse_value(&arg, argv.next())),
+                "--target-turn" => args.target_turn = Some(parse_value(&arg, argv.next())),
+                "--target-curve" => args.target_curve = Some(parse_list(&arg, argv.next())),
+                "--axes" => args.axes = parse_axes(&arg, argv.next()),
+                "--tabu" => args.tabu = Some(parse_value(&arg, argv.next())),
+                "--restart" => {
+                    let name = expect_value(&arg, argv.next());
+                    args.restart = Some(
+                        Restart::from_name(&name)
+                            .unwrap_or_else(|| panic!("Unknown restart for {arg}: {name}")),
+                    );
+                }
+                "--restart-after" => args.restart_after = Some(parse_value(&arg, argv.next())),
+                "--kick" => args.kick = Some(parse_value(&arg, argv.next())),
+                "--steepest" => args.steepest = Some(parse_count(&arg, argv.next())),
+                "--patience" => args.stopping.patience = Some(parse_value(&arg, argv.next())),
+                "--plateau" => args.stopping.plateau = Some(parse_value(&arg, argv.next())),
+                "--min-delta" => args.stopping.min_delta = Some(parse_value(&arg, argv.next())),
+                "--max-time" => args.stopping.max_time = Some(parse_value(&arg, argv.next())),
+                "--max-games" => args.stopping.max_games = Some(parse_value(&arg, argv.next())),
+                "--run" => args.run = Some(expect_value(&arg, argv.next())),
+                _ => panic!("Unknown argument: {arg}"),
+            }
+        }
+
+        args
+    }
+
+    //Search options given on the command line replace the ones of the configuration
+    pub fn search_config(&self, config: SearchConfig) -> SearchConfig {
+        SearchConfig {
+            tabu_tenure: self.tabu.unwrap_or(config.tabu_tenure),
+            restart: self.restart.unwrap_or(config.restart),
+            restart_after: self.restart_after.unwrap_or(config.restart_after),
+            kick: self.kick.unwrap_or(config.kick),
+        }
+    }
+}
+
+fn expect_value(arg: &str, value: Option<String>) -> String {
+    value.unwrap_or_else(|| panic!("Missing value for {arg}"))
+}
+
+//Accepts 3, 2..6, 2..=6 or 2-6, bounds are inclusive
+fn parse_range(arg: &str, value: Option<String>) -> RangeInclusive<u32> {
+    let value = expect_value(arg, value);
+    let bounds = value
+        .split_once("..=")
+        .or_else(|| value.split_once(".."))
+        .or_else(|| value.split_once('-'))
+        .unwrap_or((&value, &value));
+    let parse = |bound: &str| {
+        bound
+            .trim()
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid range for {arg}: {value}"))
+    };
+
+    parse(bounds.0)..=parse(bounds.1)
+}
+
+//Comma separated list: taunt,distortion,trample,first_strike
+fn parse_keywords(arg: &str, value: Option<String>) -> Vec<Keyword> {
+    expect_value(arg, value)
+        .split(',')
+        .map(|name| {
+            Keyword::from_name(name.trim())
+                .unwrap_or_else(|| panic!("Unknown keyword for {arg}: {name}"))
+        })
+        .collect()
+}
+
+//Comma separated axes name:min:max:bins, e.g. avg-cost:2:10:8,taunt:0:1:5
+pub fn parse_axes(arg: &str, value: Option<String>) -> Vec<Axis> {
+    let value = expect_value(arg, value);
+    value
+        .split(',')
+        .map(|axis| {
+            let parts: Vec<&str> = axis.trim().split(':').collect();
+            let [name, min, max, bins] = parts[..] else {
+                panic!("Invalid axis for {arg}: {axis}, expected name:min:max:bins");
+            };
+            let parse = |number: &str| {
+                number
+                    .parse::<f64>()
+                    .unwrap_or_else(|_| panic!("Invalid axis for {arg}: {axis}"))
+            };
+            let axis = Axis {
+                name: name.to_owned(),
+                descriptor: Descriptor::from_name(name)
+                    .unwrap_or_else(|| panic!("Unknown descriptor for {arg}: {name}")),
+                min: parse(min),
+                max: parse(max),
+                bins: parse(bins) as usize,
+            };
+            if axis.bins == 0 || axis.max <= axis.min {
+                panic!("Invalid axis for {arg}: {name} needs bins and max > min");
+            }
+            axis
+        })
+        .collect()
+}
+
+//Comma separated values: 2,4,6,6,5,4,3
+fn parse_list<T: std::str::FromStr>(arg: &str, value: Option<String>) -> Vec<T> {
+    let value = expect_value(arg, value);
+    value
+        .split(',')
+        .map(|item| {
+            item.trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid value for {arg}: {value}"))
+        })
+        .collect()
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: Option<String>) -> T {
+    let value = expect_value(arg, value);
+    value
+        .parse()
+        .unwrap_or_else(|_| panic!("Invalid value for {arg}: {value}"))
+}
+
+//Numbers of games, iterations, ... that must be at least 1
+fn parse_count(arg: &str, value: Option<String>) -> usize {
+    let count = parse_value(arg, value);
+    if count == 0 {
+        panic!("{arg} must be at least 1");
+    }
+    count
+}
@@ -1,8 +1,13 @@
-#![allow(non_camel_case_types, dead_code)]
+#![allow(non_camel_case_types, non_snake_case)]
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+use super::{
+    cost_model::{CostModel, DefaultCostModel},
+    effect::Effect,
+};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Card {
     Attack: u32,
     Defense: u32,
@@ -10,9 +15,35 @@ pub struct Card {
     HasDistortion: bool,
     HasTrample: bool,
     HasFirstStrike: bool,
+    #[serde(default)]
+    OnPlay: Option<Effect>,
+    #[serde(default)]
+    OnDeath: Option<Effect>,
+    //Missing costs are left to 0 and computed by the cost model when loading a card database
+    #[serde(default)]
     Cost: u32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    Taunt,
+    Distortion,
+    Trample,
+    FirstStrike,
+}
+
+impl Keyword {
+    pub fn from_name(name: &str) -> Option<Keyword> {
+        match name.to_lowercase().as_str() {
+            "taunt" | "provocation" => Some(Keyword::Taunt),
+            "distortion" => Some(Keyword::Distortion),
+            "trample" => Some(Keyword::Trample),
+            "first_strike" | "firststrike" | "first-strike" => Some(Keyword::FirstStrike),
+            _ => None,
+        }
+    }
+}
+
 pub struct PlayedCard {
     pub card: Card,
     pub defense_left: u32,
@@ -36,22 +67,36 @@ impl Card {
         trample: bool,
         first_strike: bool,
     ) -> Card {
-        Card {
+        let mut card = Card {
             Attack: attack,
             Defense: defense,
             HasTaunt: provocation,
             HasDistortion: distortion,
             HasTrample: trample,
             HasFirstStrike: first_strike,
-            Cost: Self::compute_cost(
-                attack,
-                defense,
-                provocation,
-                distortion,
-                trample,
-                first_strike,
-            ),
-        }
+            OnPlay: None,
+            OnDeath: None,
+            Cost: 0,
+        };
+        card.Cost = DefaultCostModel.compute_cost(&card);
+        card
+    }
+
+    pub fn with_cost(mut self, cost: u32) -> Card {
+        self.Cost = cost;
+        self
+    }
+
+    pub fn priced_by(mut self, cost_model: &dyn CostModel) -> Card {
+        self.Cost = cost_model.compute_cost(&self);
+        self
+    }
+
+    //Prices the card with the default model, use priced_by afterwards for another one
+    pub fn with_effects(mut self, on_play: Option<Effect>, on_death: Option<Effect>) -> Card {
+        self.OnPlay = on_play;
+        self.OnDeath = on_death;
+        self.priced_by(&DefaultCostModel)
     }
 
     pub fn get_attack(&self) -> u32 {
@@ -78,67 +123,55 @@ impl Card {
         self.HasFirstStrike
     }
 
+    pub fn has_keyword(&self, keyword: Keyword) -> bool {
+        match keyword {
+            Keyword::Taunt => self.HasTaunt,
+            Keyword::Distortion => self.HasDistortion,
+            Keyword::Trample => self.HasTrample,
+            Keyword::FirstStrike => self.HasFirstStrike,
+        }
+    }
+
+    pub fn get_on_play(&self) -> Option<Effect> {
+        self.OnPlay
+    }
+
+    pub fn get_on_death(&self) -> Option<Effect> {
+        self.OnDeath
+    }
+
     pub fn get_cost(&self) -> u32 {
         self.Cost
     }
 
-    pub fn get_name(&self) -> String {
-        let cost_name = match self.Cost {
-            1 => "Stagiaire",
-            2 => "Poussin",
-            3 => "Chaton",
-            4 => "Goblin",
-            5 => "Prog",
-            6 => "Ectolion",
-            7 => "Dragon",
-            8 => "Axel",
-            _ => "[ERROR: COST_NAME]",
-        };
-
-        let atk_name = match self.Attack {
-            0 => "Pacifiste",
-            1..=3 => "Innofensif",
-            4..=6 => "Motive",
-            7..=9 => "Hargneux",
-            10..=12 => "Violent",
-            _ => "[ERROR: ATK_NAME]",
-        };
+    //Stable identifier derived from the card's design. The cost is not part of it so that decks
+    //still load once a cost model prices the cards differently, a card pool cannot hold two cards
+    //differing only by their cost.
+    //e.g. A9D2-dr for a 9/2 with distortion and trample, A3D4+PDmg2 for a 3/4 dealing 2 on play
+    pub fn get_id(&self) -> String {
+        let mut id = format!("A{}D{}", self.Attack, self.Defense);
+
+        let keywords: String = [
+            (self.HasTaunt, 't'),
+            (self.HasDistortion, 'd'),
+            (self.HasTrample, 'r'),
+            (self.HasFirstStrike, 'f'),
+        ]
+        .iter()
+        .filter(|(has_keyword, _)| *has_keyword)
+        .map(|(_, letter)| *letter)
+        .collect();
+        if !keywords.is_empty() {
+            id += &format!("-{keywords}");
+        }
 
-        let def_name = match self.Defense {
-            1 => "en Mousse",
-            2..=4 => "en Carton",
-            5..=7 => "en Plastique",
-            8..=10 => "en Terre cuite",
-            11..=13 => "en Chene",
-            14..=16 => "de Metal",
-            _ => "[ERROR: DEF_NAME]",
-        };
+        if let Some(effect) = self.OnPlay {
+            id += &format!("+P{}", effect.get_code());
+        }
+        if let Some(effect) = self.OnDeath {
+            id += &format!("+X{}", effect.get_code());
+        }
 
-        let prov_name = if self.HasTaunt { " GD" } else { "" };
-        let dist_name = if self.HasDistortion {
-            " Parlementaire"
-        } else {
-            ""
-        };
-        let trample_name = if self.HasTrample { " Geant" } else { "" };
-        let f_strike_name = if self.HasFirstStrike { " Fourbe" } else { "" };
-
-        format!(
-            "{cost_name} {atk_name}{prov_name}{dist_name}{trample_name}{f_strike_name} {def_name}"
-        )
-    }
-
-    fn compute_cost(
-        atk: u32,
-        def: u32,
-        prov: bool,
-        dist: bool,
-        trmpl: bool,
-        f_strike: bool,
-    ) -> u32 {
-        (((atk + def) as f32 / 2.0)
-            + (prov as u32 as f32) * 1.5
-            + (dist as u32 + trmpl as u32 + f_strike as u32) as f32)
-            .ceil() as u32
+        id
     }
 }
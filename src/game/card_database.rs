@@ -0,0 +1,195 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::Write,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{card::Card, cost_model::CostModel, effect::Effect, setlist::SetList};
+
+const CSV_HEADER: &str =
+    "Id,Attack,Defense,Taunt,Distortion,Trample,FirstStrike,OnPlay,OnDeath,Cost";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CardEntry {
+    //Optional in files, it is always derived from the card and only checked when present
+    #[serde(default)]
+    id: String,
+    #[serde(flatten)]
+    card: Card,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DatabaseFile {
+    cards: Vec<CardEntry>,
+}
+
+//Cards with unique ids, stored as JSON ({"Cards": [...]}) or CSV depending on the extension
+#[derive(Debug, Default, Clone)]
+pub struct CardDatabase {
+    cards: Vec<Card>,
+    ids: HashSet<String>,
+}
+
+impl CardDatabase {
+    pub fn from_setlist(setlist: &SetList) -> CardDatabase {
+        let mut database = CardDatabase::default();
+        for card in setlist.iter() {
+            database.push(*card);
+        }
+        database
+    }
+
+    //Cards without a cost are priced by the cost model
+    pub fn load(path: &str, cost_model: &dyn CostModel) -> CardDatabase {
+        let content = fs::read_to_string(path).unwrap();
+        let entries = if path.ends_with(".csv") {
+            parse_csv(&content, path)
+        } else {
+            serde_json::from_str::<DatabaseFile>(&content)
+                .unwrap()
+                .cards
+        };
+
+        let mut database = CardDatabase::default();
+        for mut entry in entries {
+            if entry.card.get_cost() == 0 {
+                entry.card = entry.card.priced_by(cost_model);
+            }
+            let id = entry.card.get_id();
+            if !entry.id.is_empty() && entry.id != id {
+                panic!(
+                    "{path}: card id {} does not match its stats ({id})",
+                    entry.id
+                );
+            }
+            database.push(entry.card);
+        }
+
+        database
+    }
+
+    pub fn save(&self, path: &str) {
+        let mut file = File::create(path).unwrap();
+
+        if path.ends_with(".csv") {
+            file.write_all(format!("{CSV_HEADER}\n").as_bytes())
+                .unwrap();
+            for card in &self.cards {
+                let effect_code =
+                    |effect: Option<Effect>| effect.map_or(String::new(), |e| e.get_code());
+                file.write_all(
+                    format!(
+                        "{},{},{},{},{},{},{},{},{},{}\n",
+                        card.get_id(),
+                        card.get_attack(),
+                        card.get_defense(),
+                        card.get_taunt() as u32,
+                        card.get_distortion() as u32,
+                        card.get_trample() as u32,
+                        card.get_first_strike() as u32,
+                        effect_code(card.get_on_play()),
+                        effect_code(card.get_on_death()),
+                        card.get_cost(),
+                    )
+                    .as_bytes(),
+                )
+                .unwrap();
+            }
+        } else {
+            let database_file = DatabaseFile {
+                cards: self
+                    .cards
+                    .iter()
+                    .map(|card| CardEntry {
+                        id: card.get_id(),
+                        card: *card,
+                    })
+                    .collect(),
+            };
+            file.write_all(
+                serde_json::to_string_pretty(&database_file)
+                    .unwrap()
+                    .as_bytes(),
+            )
+            .unwrap();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn to_setlist(&self) -> SetList {
+        self.cards.clone().into_boxed_slice()
+    }
+
+    fn push(&mut self, card: Card) {
+        let id = card.get_id();
+        if !self.ids.insert(id.clone()) {
+            panic!("Duplicate card id {id}");
+        }
+        self.cards.push(card);
+    }
+}
+
+fn parse_csv(content: &str, path: &str) -> Vec<CardEntry> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = lines
+        .next()
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .collect();
+    let column = |name: &str| header.iter().position(|column| *column == name);
+
+    lines
+        .map(|line| {
+            let values: Vec<&str> = line.split(',').map(str::trim).collect();
+            let value = |name: &str| {
+                column(name)
+                    .and_then(|i| values.get(i))
+                    .copied()
+                    .unwrap_or("")
+            };
+            let number = |name: &str| -> u32 {
+                let value = value(name);
+                if value.is_empty() {
+                    return 0;
+                }
+                value
+                    .parse()
+                    .unwrap_or_else(|_| panic!("{path}: invalid {name} '{value}' in '{line}'"))
+            };
+            let effect = |name: &str| {
+                let code = value(name);
+                if code.is_empty() {
+                    return None;
+                }
+                Some(
+                    Effect::from_code(code)
+                        .unwrap_or_else(|| panic!("{path}: invalid {name} '{code}' in '{line}'")),
+                )
+            };
+
+            let card = Card::new(
+                number("Attack"),
+                number("Defense"),
+                number("Taunt") != 0,
+                number("Distortion") != 0,
+                number("Trample") != 0,
+                number("FirstStrike") != 0,
+            )
+            .with_effects(effect("OnPlay"), effect("OnDeath"))
+            .with_cost(number("Cost"));
+
+            CardEntry {
+                id: value("Id").to_owned(),
+                card,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,30 @@
+use std::ops::RangeInclusive;
+
+use super::card::{Card, Keyword};
+
+//Restricts a card pool, every condition must hold for a card to be kept
+#[derive(Debug, Default, Clone)]
+pub struct CardFilter {
+    pub cost: Option<RangeInclusive<u32>>,
+    pub attack: Option<RangeInclusive<u32>>,
+    pub defense: Option<RangeInclusive<u32>>,
+    pub with: Vec<Keyword>,
+    pub without: Vec<Keyword>,
+}
+
+impl CardFilter {
+    pub fn matches(&self, card: &Card) -> bool {
+        let in_range = |range: &Option<RangeInclusive<u32>>, value| {
+            range.as_ref().is_none_or(|range| range.contains(&value))
+        };
+
+        in_range(&self.cost, card.get_cost())
+            && in_range(&self.attack, card.get_attack())
+            && in_range(&self.defense, card.get_defense())
+            && self.with.iter().all(|keyword| card.has_keyword(*keyword))
+            && !self
+                .without
+                .iter()
+                .any(|keyword| card.has_keyword(*keyword))
+    }
+}
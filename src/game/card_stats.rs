@@ -0,0 +1,139 @@
+use std::{
+    collections::{hash_map, HashMap},
+    fs::File,
+    io::Write,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{card::Card, naming::CardNames};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct CardCounters {
+    pub drawn: u32,
+    pub played: u32,
+    pub turns_on_board: u32,
+    pub face_damage: u32,
+    pub kills: u32,
+    //Games in which the card was played at least once, and how many of them were won
+    pub games_played: u32,
+    pub wins_when_played: u32,
+}
+
+impl CardCounters {
+    pub fn merge(&mut self, other: &CardCounters) {
+        self.drawn += other.drawn;
+        self.played += other.played;
+        self.turns_on_board += other.turns_on_board;
+        self.face_damage += other.face_damage;
+        self.kills += other.kills;
+        self.games_played += other.games_played;
+        self.wins_when_played += other.wins_when_played;
+    }
+
+    pub fn win_rate_when_played(&self) -> f64 {
+        if self.games_played == 0 {
+            return 0.0;
+        }
+        self.wins_when_played as f64 / self.games_played as f64
+    }
+}
+
+//Serialized as a list of (card, counters) pairs sorted by card id since cards cannot be JSON keys
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(from = "Vec<(Card, CardCounters)>", into = "Vec<(Card, CardCounters)>")]
+pub struct CardStats {
+    counters: HashMap<Card, CardCounters>,
+}
+
+impl From<Vec<(Card, CardCounters)>> for CardStats {
+    fn from(counters: Vec<(Card, CardCounters)>) -> CardStats {
+        CardStats {
+            counters: counters.into_iter().collect(),
+        }
+    }
+}
+
+impl From<CardStats> for Vec<(Card, CardCounters)> {
+    fn from(card_stats: CardStats) -> Vec<(Card, CardCounters)> {
+        let mut counters: Vec<_> = card_stats.counters.into_iter().collect();
+        counters.sort_by_cached_key(|(card, _)| card.get_id());
+        counters
+    }
+}
+
+impl CardStats {
+    pub fn counters(&mut self, card: &Card) -> &mut CardCounters {
+        self.counters.entry(*card).or_default()
+    }
+
+    //Counters of a card that never showed up are all 0
+    pub fn get(&self, card: &Card) -> CardCounters {
+        self.counters.get(card).copied().unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> hash_map::Iter<'_, Card, CardCounters> {
+        self.counters.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn merge(&mut self, other: &CardStats) {
+        for (card, counters) in other.iter() {
+            self.counters(card).merge(counters);
+        }
+    }
+
+    //Called once per game on the stats of a single player
+    pub(crate) fn finish_game(&mut self, won: bool) {
+        for counters in self.counters.values_mut() {
+            if counters.played > 0 {
+                counters.games_played = 1;
+                counters.wins_when_played = won as u32;
+            }
+        }
+    }
+
+    pub fn export_csv(&self, names: &CardNames, file: &mut File) {
+        file.write_all(
+            b"Id,Name,Cost,Attack,Defense,Taunt,Distortion,Trample,FirstStrike,\
+Drawn,Played,TurnsOnBoard,FaceDamage,Kills,GamesPlayed,WinRateWhenPlayed\n",
+        )
+        .unwrap();
+
+        let mut rows: Vec<_> = self.iter().collect();
+        rows.sort_by(|(_, a), (_, b)| {
+            b.win_rate_when_played()
+                .total_cmp(&a.win_rate_when_played())
+        });
+
+        for (card, counters) in rows {
+            file.write_all(
+                format!(
+                    "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{:.4}\n",
+                    card.get_id(),
+                    names.label(card),
+                    card.get_cost(),
+                    card.get_attack(),
+                    card.get_defense(),
+                    card.get_taunt() as u32,
+                    card.get_distortion() as u32,
+                    card.get_trample() as u32,
+                    card.get_first_strike() as u32,
+                    counters.drawn,
+                    counters.played,
+                    counters.turns_on_board,
+                    counters.face_damage,
+                    counters.kills,
+                    counters.games_played,
+                    counters.win_rate_when_played(),
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+        }
+    }
+}
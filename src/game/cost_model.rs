@@ -0,0 +1,97 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use super::card::Card;
+
+pub trait CostModel {
+    fn compute_cost(&self, card: &Card) -> u32;
+}
+
+//ceil((atk + def) / 2 + 1.5 * taunt + distortion + trample + first_strike + effects)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultCostModel;
+
+impl CostModel for DefaultCostModel {
+    fn compute_cost(&self, card: &Card) -> u32 {
+        WeightedCostModel::default().compute_cost(card)
+    }
+}
+
+//Per-keyword weights, stats are raised to `stat_exponent` before being weighted
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct WeightedCostModel {
+    pub base: f32,
+    pub attack: f32,
+    pub defense: f32,
+    pub stat_exponent: f32,
+    pub taunt: f32,
+    pub distortion: f32,
+    pub trample: f32,
+    pub first_strike: f32,
+    pub effect: f32,
+}
+
+impl Default for WeightedCostModel {
+    fn default() -> Self {
+        WeightedCostModel {
+            base: 0.0,
+            attack: 0.5,
+            defense: 0.5,
+            stat_exponent: 1.0,
+            taunt: 1.5,
+            distortion: 1.0,
+            trample: 1.0,
+            first_strike: 1.0,
+            effect: 1.0,
+        }
+    }
+}
+
+impl CostModel for WeightedCostModel {
+    fn compute_cost(&self, card: &Card) -> u32 {
+        let effects_cost = card.get_on_play().map_or(0.0, |effect| effect.cost())
+            + card.get_on_death().map_or(0.0, |effect| effect.cost());
+
+        let cost = self.base
+            + self.attack * (card.get_attack() as f32).powf(self.stat_exponent)
+            + self.defense * (card.get_defense() as f32).powf(self.stat_exponent)
+            + self.taunt * card.get_taunt() as u32 as f32
+            + self.distortion * card.get_distortion() as u32 as f32
+            + self.trample * card.get_trample() as u32 as f32
+            + self.first_strike * card.get_first_strike() as u32 as f32
+            + self.effect * effects_cost;
+
+        cost.max(0.0).ceil() as u32
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(tag = "Model")]
+pub enum CostModelConfig {
+    Default,
+    Weighted(WeightedCostModel),
+}
+
+impl CostModelConfig {
+    pub fn load(path: &str) -> CostModelConfig {
+        let content = fs::read_to_string(path).unwrap();
+        serde_json::from_str(&content).unwrap()
+    }
+
+    //Weights equivalent to the configured model
+    pub fn weights(&self) -> WeightedCostModel {
+        match self {
+            CostModelConfig::Default => WeightedCostModel::default(),
+            CostModelConfig::Weighted(model) => *model,
+        }
+    }
+
+    pub fn build(self) -> Box<dyn CostModel> {
+        match self {
+            CostModelConfig::Default => Box::new(DefaultCostModel),
+            CostModelConfig::Weighted(model) => Box::new(model),
+        }
+    }
+}
@@ -1,19 +1,38 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+};
+
 use super::card::Card;
-use super::setlist::SetList;
-use rand::seq::SliceRandom;
+use super::setlist::{SetList, SetListTrait};
+use rand::{seq::SliceRandom, Rng};
 
 pub type Deck = [Card; 30];
 
+//Maximum number of copies of a card in a deck
+pub const MAX_COPIES: usize = 3;
+
 pub trait DeckTrait {
-    fn new(set_list: &SetList) -> Deck;
-    fn deck_cost(&self) -> [u32; 30];
+    fn new<R: Rng + ?Sized>(set_list: &SetList, rng: &mut R) -> Self;
+    fn load(path: &str, set_list: &SetList) -> Self;
+    fn save(&self, file: &mut File);
 }
 
 impl DeckTrait for Deck {
-    fn new(set_list: &SetList) -> Deck {
+    //Pools smaller than a deck are used several times, up to MAX_COPIES of each card
+    fn new<R: Rng + ?Sized>(set_list: &SetList, rng: &mut R) -> Deck {
+        if set_list.len() * MAX_COPIES < 30 {
+            panic!(
+                "A pool of {} cards cannot fill a deck of 30 with at most {MAX_COPIES} copies",
+                set_list.len()
+            );
+        }
+
         let mut cards: [Card; 30] = [Card::default(); 30];
-        let mut card_indexes = Vec::from_iter(0..set_list.len());
-        card_indexes.shuffle(&mut rand::thread_rng());
+        let copies = if set_list.len() < 30 { MAX_COPIES } else { 1 };
+        let mut card_indexes =
+            Vec::from_iter((0..set_list.len() * copies).map(|i| i % set_list.len()));
+        card_indexes.shuffle(rng);
 
         for i in 0..30 {
             cards[i] = set_list[card_indexes[i]];
@@ -22,11 +41,35 @@ impl DeckTrait for Deck {
         cards
     }
 
-    fn deck_cost(&self) -> [u32; 30] {
-        let mut costs: [u32; 30] = [0; 30];
-        for (i, card) in self.iter().enumerate() {
-            costs[i] = card.get_cost();
-        }
-        costs
+    //Reads the {"Ids": [...]} format written by save, every id must be a card of the set list
+    fn load(path: &str, set_list: &SetList) -> Deck {
+        let content = fs::read_to_string(path).unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&content).unwrap();
+        let ids: Vec<String> = serde_json::from_value(value["Ids"].take())
+            .unwrap_or_else(|error| panic!("{path}: invalid or missing Ids: {error}"));
+
+        let cards = set_list.by_id();
+        let cards: Vec<Card> = ids
+            .iter()
+            .map(|id| {
+                *cards
+                    .get(id)
+                    .unwrap_or_else(|| panic!("{path}: card {id} is not in the card pool"))
+            })
+            .collect();
+        cards.try_into().unwrap_or_else(|cards: Vec<Card>| {
+            panic!("{path} has {} cards instead of 30", cards.len())
+        })
+    }
+
+    fn save(&self, file: &mut File) {
+        file.write_all(
+            format!(
+                "{{\n\t\"Ids\": {}\n}}",
+                serde_json::to_string(&self.map(|card| card.get_id())).unwrap()
+            )
+            .as_bytes(),
+        )
+        .unwrap();
     }
 }
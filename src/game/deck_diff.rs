@@ -0,0 +1,67 @@
+use std::{fs::File, io::Write};
+
+use super::{card::Card, deck::Deck, naming::CardNames};
+
+//Copies of a card in each deck
+#[derive(Debug, Clone, Copy)]
+pub struct CardDifference {
+    pub card: Card,
+    pub count_a: usize,
+    pub count_b: usize,
+}
+
+impl CardDifference {
+    //Copies added to go from deck A to deck B, negative when copies are removed
+    pub fn difference(&self) -> i64 {
+        self.count_b as i64 - self.count_a as i64
+    }
+}
+
+//Every card of either deck, removed cards first, then added cards, then the cards both decks hold
+//the same number of times
+pub fn diff(a: &Deck, b: &Deck) -> Vec<CardDifference> {
+    let mut differences: Vec<CardDifference> = vec![];
+    for card in a.iter().chain(b.iter()) {
+        if differences
+            .iter()
+            .any(|difference| difference.card == *card)
+        {
+            continue;
+        }
+        differences.push(CardDifference {
+            card: *card,
+            count_a: a.iter().filter(|other| *other == card).count(),
+            count_b: b.iter().filter(|other| *other == card).count(),
+        });
+    }
+
+    differences.sort_by_key(|difference| {
+        let order = match difference.difference() {
+            ..0 => 0,
+            0 => 2,
+            _ => 1,
+        };
+        (order, difference.card.get_cost(), difference.card.get_id())
+    });
+    differences
+}
+
+pub fn export_csv(differences: &[CardDifference], names: &CardNames, file: &mut File) {
+    file.write_all(b"Id,Name,Cost,CountA,CountB,Difference\n")
+        .unwrap();
+    for difference in differences {
+        file.write_all(
+            format!(
+                "{},{},{},{},{},{}\n",
+                difference.card.get_id(),
+                names.label(&difference.card),
+                difference.card.get_cost(),
+                difference.count_a,
+                difference.count_b,
+                difference.difference()
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    }
+}
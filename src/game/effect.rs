@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use super::card::Card;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Effect {
+    DealDamage(u32),
+    Draw(u32),
+    SummonToken(u32, u32),
+}
+
+impl Effect {
+    pub fn cost(&self) -> f32 {
+        match *self {
+            Effect::DealDamage(amount) => amount as f32 / 2.0,
+            Effect::Draw(amount) => amount as f32,
+            Effect::SummonToken(attack, defense) => (attack + defense) as f32 / 2.0,
+        }
+    }
+
+    //Short code used in card ids and card database files: Dmg2, Draw1, Tok1x1
+    pub fn get_code(&self) -> String {
+        match *self {
+            Effect::DealDamage(amount) => format!("Dmg{amount}"),
+            Effect::Draw(amount) => format!("Draw{amount}"),
+            Effect::SummonToken(attack, defense) => format!("Tok{attack}x{defense}"),
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Effect> {
+        if let Some(amount) = code.strip_prefix("Dmg") {
+            return amount.parse().ok().map(Effect::DealDamage);
+        }
+        if let Some(amount) = code.strip_prefix("Draw") {
+            return amount.parse().ok().map(Effect::Draw);
+        }
+        let (attack, defense) = code.strip_prefix("Tok")?.split_once('x')?;
+        Some(Effect::SummonToken(
+            attack.parse().ok()?,
+            defense.parse().ok()?,
+        ))
+    }
+
+    //Tokens are plain creatures, they never carry effects themselves
+    pub fn token(attack: u32, defense: u32) -> Card {
+        Card::new(attack, defense, false, false, false, false)
+    }
+}
@@ -0,0 +1,97 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use super::card::{Card, Keyword};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "Rule", rename_all_fields = "PascalCase")]
+pub enum GenerationRule {
+    //The keyword is only given to cards with at least this attack
+    MinAttack { keyword: Keyword, min_attack: u32 },
+    //Cards with at most this attack must have the keyword
+    RequiredBelow { keyword: Keyword, max_attack: u32 },
+    //The two keywords are never given to the same card
+    Incompatible { keywords: [Keyword; 2] },
+}
+
+impl GenerationRule {
+    fn allows(&self, card: &Card) -> bool {
+        match *self {
+            GenerationRule::MinAttack {
+                keyword,
+                min_attack,
+            } => !card.has_keyword(keyword) || card.get_attack() >= min_attack,
+            GenerationRule::RequiredBelow {
+                keyword,
+                max_attack,
+            } => card.get_attack() > max_attack || card.has_keyword(keyword),
+            GenerationRule::Incompatible { keywords: [a, b] } => {
+                !(card.has_keyword(a) && card.has_keyword(b))
+            }
+        }
+    }
+}
+
+//Stat ranges are inclusive, cards costing more than max_cost are not generated
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct GeneratorConfig {
+    pub attack: [u32; 2],
+    pub defense: [u32; 2],
+    pub max_cost: u32,
+    pub keywords: Vec<Keyword>,
+    pub rules: Vec<GenerationRule>,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            attack: [0, 15],
+            defense: [1, 16],
+            max_cost: 8,
+            keywords: vec![
+                Keyword::Taunt,
+                Keyword::Distortion,
+                Keyword::Trample,
+                Keyword::FirstStrike,
+            ],
+            rules: vec![
+                GenerationRule::RequiredBelow {
+                    keyword: Keyword::Taunt,
+                    max_attack: 0,
+                },
+                GenerationRule::MinAttack {
+                    keyword: Keyword::FirstStrike,
+                    min_attack: 1,
+                },
+                GenerationRule::MinAttack {
+                    keyword: Keyword::Trample,
+                    min_attack: 2,
+                },
+            ],
+        }
+    }
+}
+
+impl GeneratorConfig {
+    pub fn load(path: &str) -> GeneratorConfig {
+        let content = fs::read_to_string(path).unwrap();
+        serde_json::from_str(&content).unwrap()
+    }
+
+    pub fn allows(&self, card: &Card) -> bool {
+        let uses_disabled_keyword = [
+            Keyword::Taunt,
+            Keyword::Distortion,
+            Keyword::Trample,
+            Keyword::FirstStrike,
+        ]
+        .iter()
+        .any(|keyword| card.has_keyword(*keyword) && !self.keywords.contains(keyword));
+
+        !uses_disabled_keyword
+            && card.get_cost() <= self.max_cost
+            && self.rules.iter().all(|rule| rule.allows(card))
+    }
+}
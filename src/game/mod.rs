@@ -1,15 +1,26 @@
 pub mod card;
+pub mod card_database;
+pub mod card_filter;
+pub mod card_stats;
+pub mod cost_model;
 pub mod deck;
+pub mod deck_diff;
+pub mod effect;
+pub mod generation;
+pub mod naming;
 pub mod setlist;
 pub mod weighted_setlist;
 
 use card::Card;
+use card_stats::{CardCounters, CardStats};
 use deck::Deck;
+use effect::Effect;
 use setlist::SetList;
 
-use self::{card::PlayedCard, setlist::SetListTrait};
+use self::card::PlayedCard;
 use crate::game::deck::DeckTrait;
-use rand::prelude::SliceRandom;
+use rand::{prelude::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
 #[derive(Clone)]
 pub struct Player {
@@ -21,10 +32,7 @@ pub struct Player {
     card_to_draw: usize,
     hand: Vec<Card>,
     board: Vec<Card>,
-}
-
-pub trait Queue {
-    fn pop(&self);
+    card_stats: Option<CardStats>,
 }
 
 impl Player {
@@ -32,13 +40,44 @@ impl Player {
         Player {
             pv: 100,
             base_deck: deck,
-            deck: deck,
+            deck,
             mana: 0,
             max_mana: 0,
             card_to_draw: 0,
             hand: Vec::with_capacity(30),
             board: Vec::with_capacity(30),
+            card_stats: None,
+        }
+    }
+
+    fn record(&mut self, card: &Card, update: impl FnOnce(&mut CardCounters)) {
+        if let Some(card_stats) = &mut self.card_stats {
+            update(card_stats.counters(card));
+        }
+    }
+
+    fn draw_card(&mut self) {
+        if self.card_to_draw >= 30 {
+            return;
+        }
+
+        let drawn_card = self.deck[self.card_to_draw];
+        self.card_to_draw += 1;
+        self.record(&drawn_card, |counters| counters.drawn += 1);
+        self.insert_in_hand(drawn_card);
+    }
+
+    fn insert_in_hand(&mut self, drawn_card: Card) {
+        //Insert card in order
+        let mut index = self.hand.len();
+        for (i, card) in self.hand.iter().enumerate() {
+            if card.get_cost() <= drawn_card.get_cost() {
+                index = i;
+                break;
+            }
         }
+
+        self.hand.insert(index, drawn_card);
     }
 }
 
@@ -46,156 +85,195 @@ pub struct Game {
     pub p1: Player,
     pub p2: Player,
     pub setlist: SetList,
+    pub track_card_stats: bool,
+    //Shuffles the decks, seeded games are reproducible
+    pub rng: ChaCha8Rng,
 }
 
 #[derive(Debug)]
 pub struct Stats {
     pub player1_won: bool,
     pub nb_turns: u32,
-    pub deck_cost: [u32; 30],
+    //Only filled when the game tracks card stats
+    pub p1_card_stats: Option<CardStats>,
+    pub p2_card_stats: Option<CardStats>,
 }
 
 impl Game {
-    pub fn new() -> Game {
-        let setlist = SetList::gen_all();
-        let deck1 = Deck::new(&setlist);
-        let deck2 = Deck::new(&setlist);
+    pub fn with_setlist(setlist: SetList) -> Game {
+        Game::with_rng(setlist, ChaCha8Rng::from_entropy())
+    }
+
+    pub fn seeded(setlist: SetList, seed: u64) -> Game {
+        Game::with_rng(setlist, ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    fn with_rng(setlist: SetList, mut rng: ChaCha8Rng) -> Game {
+        let deck1 = Deck::new(&setlist, &mut rng);
+        let deck2 = Deck::new(&setlist, &mut rng);
 
         Game {
             p1: Player::new(deck1),
             p2: Player::new(deck2),
             setlist,
+            track_card_stats: false,
+            rng,
         }
     }
 
     pub fn play(&mut self) -> Stats {
         self.p1 = Player::new(self.p1.base_deck);
         self.p2 = Player::new(self.p2.base_deck);
+        if self.track_card_stats {
+            self.p1.card_stats = Some(CardStats::default());
+            self.p2.card_stats = Some(CardStats::default());
+        }
 
-        self.p1.deck.shuffle(&mut rand::thread_rng());
-        self.p2.deck.shuffle(&mut rand::thread_rng());
+        self.p1.deck.shuffle(&mut self.rng);
+        self.p2.deck.shuffle(&mut self.rng);
 
         //Draw 3 cards
         for _ in 0..3 {
-            let drawn_card = &self.p1.deck[self.p1.card_to_draw];
-            self.p1.card_to_draw += 1;
-
-            //Insert card in order
-            let mut index = self.p1.hand.len();
-            for (i, card) in self.p1.hand.iter().enumerate() {
-                if card.get_cost() <= drawn_card.get_cost() {
-                    index = i;
-                    break;
-                }
-            }
-
-            self.p1.hand.insert(index, *drawn_card);
+            self.p1.draw_card();
         }
 
         for _ in 0..3 {
-            let drawn_card = &self.p2.deck[self.p2.card_to_draw];
-            self.p2.card_to_draw += 1;
-
-            //Insert card in order
-            let mut index = self.p2.hand.len();
-            for (i, card) in self.p2.hand.iter().enumerate() {
-                if card.get_cost() <= drawn_card.get_cost() {
-                    index = i;
-                    break;
-                }
-            }
-
-            self.p2.hand.insert(index, *drawn_card);
+            self.p2.draw_card();
         }
 
         let mut turn_nb = 0;
         loop {
             if Self::do_turn(&mut self.p1, &mut self.p2) {
-                return Stats {
-                    player1_won: true,
-                    nb_turns: turn_nb,
-                    deck_cost: self.p1.base_deck.deck_cost(),
-                };
+                return self.end_game(true, turn_nb);
             }
 
             if Self::do_turn(&mut self.p2, &mut self.p1) {
-                return Stats {
-                    player1_won: false,
-                    nb_turns: turn_nb,
-                    deck_cost: self.p2.base_deck.deck_cost(),
-                };
+                return self.end_game(false, turn_nb);
             }
 
             turn_nb += 1;
         }
     }
 
+    fn end_game(&mut self, player1_won: bool, nb_turns: u32) -> Stats {
+        let mut p1_card_stats = self.p1.card_stats.take();
+        let mut p2_card_stats = self.p2.card_stats.take();
+        if let Some(card_stats) = &mut p1_card_stats {
+            card_stats.finish_game(player1_won);
+        }
+        if let Some(card_stats) = &mut p2_card_stats {
+            card_stats.finish_game(!player1_won);
+        }
+
+        Stats {
+            player1_won,
+            nb_turns,
+            p1_card_stats,
+            p2_card_stats,
+        }
+    }
+
     fn do_turn(p1: &mut Player, p2: &mut Player) -> bool {
         //Set mana
         p1.max_mana += 1;
         p1.mana = p1.max_mana;
 
         //Draw card in hand
-        if p1.card_to_draw < 30 {
-            let drawn_card = &p1.deck[p1.card_to_draw];
-            p1.card_to_draw += 1;
-
-            //Insert card in order
-            let mut index = p1.hand.len();
-            for (i, card) in p1.hand.iter().enumerate() {
-                if card.get_cost() <= drawn_card.get_cost() {
-                    index = i;
-                    break;
-                }
-            }
-
-            p1.hand.insert(index, *drawn_card);
-        }
+        p1.draw_card();
 
         //Place cards
         let mut new_hand = Vec::with_capacity(30);
-        for card in p1.hand.iter() {
+        let hand = std::mem::take(&mut p1.hand);
+        for card in hand {
             if card.get_cost() > p1.mana {
-                new_hand.push(*card);
+                new_hand.push(card);
                 continue;
             }
 
-            p1.board.push(*card);
+            p1.board.push(card);
+            p1.record(&card, |counters| counters.played += 1);
 
             p1.mana -= card.get_cost();
+
+            if let Some(effect) = card.get_on_play() {
+                if let Some(token) = Self::trigger(effect, &card, p1, p2) {
+                    p1.board.push(token);
+                }
+            }
         }
 
-        p1.hand = new_hand.iter().cloned().collect();
+        //Cards drawn by effects are added back to the remaining hand
+        let drawn_cards = std::mem::replace(&mut p1.hand, new_hand);
+        for card in drawn_cards {
+            p1.insert_in_hand(card);
+        }
 
-        //Attack opposite player
+        //Attack opposite player. Defenders keep their damage from one attacker to the next and
+        //leave the fight once dead, only the defenders that fought and survived stay on the board.
         let mut new_board = vec![];
         let mut new_opposite_board = vec![];
-        for card in &p1.board {
-            let mut has_attacked = false;
-            let mut overflow = 0;
-            let opposit_board = p2.board.iter().map(|card| PlayedCard::new(card.clone()));
-            for mut opposit_card in opposit_board {
-                if should_attack_card(card, &opposit_card.card) {
-                    let result = resolve_card_fight(&card, &opposit_card);
-                    if result.card_a_survived {
-                        new_board.push(*card);
-                    }
-                    if result.card_b_survived {
-                        new_opposite_board.push(opposit_card.card);
-                        opposit_card.defense_left -= card.get_attack();
+        let mut defenders: Vec<(PlayedCard, bool)> = p2
+            .board
+            .iter()
+            .map(|card| (PlayedCard::new(*card), false))
+            .collect();
+        let board = std::mem::take(&mut p1.board);
+        for card in &board {
+            p1.record(card, |counters| counters.turns_on_board += 1);
+            let target = defenders
+                .iter()
+                .position(|(defender, _)| should_attack_card(card, &defender.card));
+            let Some(i) = target else {
+                p2.pv -= card.get_attack() as i32;
+                p1.record(card, |counters| counters.face_damage += card.get_attack());
+                continue;
+            };
+
+            let defender = defenders[i].0.card;
+            let result = resolve_card_fight(card, &defenders[i].0);
+            if card.get_trample() && result.overflow > 0 {
+                p2.pv -= result.overflow;
+                p1.record(card, |counters| {
+                    counters.face_damage += result.overflow as u32
+                });
+            }
+
+            //Kills and death triggers
+            if result.card_a_survived {
+                new_board.push(*card);
+            } else {
+                p2.record(&defender, |counters| counters.kills += 1);
+                if let Some(effect) = card.get_on_death() {
+                    if let Some(token) = Self::trigger(effect, card, p1, p2) {
+                        new_board.push(token);
                     }
-                    overflow = result.overflow;
-                    has_attacked = true;
-                    break;
                 }
             }
-            if !has_attacked {
-                p2.pv -= card.get_attack() as i32;
-            }
-            if card.get_trample() && overflow > 0 {
-                p2.pv -= overflow;
+            if result.card_b_survived {
+                //No damage is dealt when the defender strikes first and kills the attacker
+                let struck_first = defender.get_first_strike()
+                    && !card.get_first_strike()
+                    && !result.card_a_survived;
+                if !struck_first {
+                    defenders[i].0.defense_left -= card.get_attack();
+                }
+                defenders[i].1 = true;
+            } else {
+                defenders.remove(i);
+                p1.record(card, |counters| counters.kills += 1);
+                if let Some(effect) = defender.get_on_death() {
+                    if let Some(token) = Self::trigger(effect, &defender, p2, p1) {
+                        new_opposite_board.push(token);
+                    }
+                }
             }
         }
+        new_opposite_board.extend(
+            defenders
+                .iter()
+                .filter(|(_, fought)| *fought)
+                .map(|(defender, _)| defender.card),
+        );
         p1.board = new_board;
         p2.board = new_opposite_board;
 
@@ -203,6 +281,67 @@ impl Game {
         p2.pv <= 0
     }
 
+    //Applies the effect for its owner, returns the token to put on the board if any
+    fn trigger(
+        effect: Effect,
+        source: &Card,
+        owner: &mut Player,
+        opponent: &mut Player,
+    ) -> Option<Card> {
+        match effect {
+            Effect::DealDamage(amount) => {
+                opponent.pv -= amount as i32;
+                owner.record(source, |counters| counters.face_damage += amount);
+                None
+            }
+            Effect::Draw(amount) => {
+                for _ in 0..amount {
+                    owner.draw_card();
+                }
+                None
+            }
+            Effect::SummonToken(attack, defense) => Some(Effect::token(attack, defense)),
+        }
+    }
+
+    //Card stats are only collected when the game tracks them
+    pub fn play_games(&mut self, n: usize) -> (u32, u32, [CardStats; 2]) {
+        let mut player1_win_count = 0;
+        let mut total_turns = 0;
+        let mut card_stats = [CardStats::default(), CardStats::default()];
+        for _ in 0..n {
+            let s = self.play();
+            player1_win_count += s.player1_won as u32;
+            total_turns += s.nb_turns;
+            for (total, stats) in card_stats
+                .iter_mut()
+                .zip([s.p1_card_stats, s.p2_card_stats])
+            {
+                if let Some(stats) = stats {
+                    total.merge(&stats);
+                }
+            }
+        }
+
+        (player1_win_count, total_turns, card_stats)
+    }
+
+    //Win rate of p1's base deck over n games as first player and n games as second player
+    pub fn win_rate(&mut self, n_games: usize) -> f64 {
+        let mut wins = 0;
+        for _ in 0..n_games {
+            wins += self.play().player1_won as usize;
+        }
+
+        std::mem::swap(&mut self.p1, &mut self.p2);
+        for _ in 0..n_games {
+            wins += !self.play().player1_won as usize;
+        }
+        std::mem::swap(&mut self.p1, &mut self.p2);
+
+        wins as f64 / (n_games * 2) as f64
+    }
+
     pub fn switch_player(&mut self) {
         let p1 = self.p1.clone();
         self.p1 = self.p2.clone();
@@ -240,9 +379,16 @@ fn resolve_card_fight(card_a: &Card, card_b: &PlayedCard) -> CardFightResult {
         can_card_b_survive
     };
 
+    //Damage beyond the defense left of the defender, none when it survives
+    let overflow = if card_b_survived {
+        0
+    } else {
+        card_a.get_attack() as i32 - card_b.defense_left as i32
+    };
+
     CardFightResult {
         card_a_survived,
         card_b_survived,
-        overflow: card_b.defense_left as i32 - card_a.get_attack() as i32,
+        overflow,
     }
 }
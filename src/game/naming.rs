@@ -0,0 +1,141 @@
+use std::{fmt, fs};
+
+use serde::{Deserialize, Serialize};
+
+use super::{card::Card, effect::Effect};
+
+const FRENCH: &str = include_str!("../../data/names/fr.json");
+const ENGLISH: &str = include_str!("../../data/names/en.json");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+//Value of the card that no range of the table covers
+pub enum NamingError {
+    Cost(u32),
+    Attack(u32),
+    Defense(u32),
+}
+
+impl fmt::Display for NamingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NamingError::Cost(cost) => write!(f, "no name for cost {cost}"),
+            NamingError::Attack(attack) => write!(f, "no name for attack {attack}"),
+            NamingError::Defense(defense) => write!(f, "no name for defense {defense}"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+struct NameRange {
+    min: u32,
+    max: u32,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+struct EffectNames {
+    deal_damage: String,
+    draw: String,
+    summon_token: String,
+}
+
+impl EffectNames {
+    fn get(&self, effect: Option<Effect>) -> &str {
+        match effect {
+            Some(Effect::DealDamage(_)) => &self.deal_damage,
+            Some(Effect::Draw(_)) => &self.draw,
+            Some(Effect::SummonToken(_, _)) => &self.summon_token,
+            None => "",
+        }
+    }
+}
+
+//Naming tables of a locale, Format places {cost}, {attack}, {keywords} and {defense}
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct CardNames {
+    format: String,
+    cost: Vec<NameRange>,
+    attack: Vec<NameRange>,
+    defense: Vec<NameRange>,
+    taunt: String,
+    distortion: String,
+    trample: String,
+    first_strike: String,
+    on_play: EffectNames,
+    on_death: EffectNames,
+}
+
+impl CardNames {
+    //Built-in locales: fr and en
+    pub fn locale(locale: &str) -> Option<CardNames> {
+        let content = match locale {
+            "fr" => FRENCH,
+            "en" => ENGLISH,
+            _ => return None,
+        };
+        Some(serde_json::from_str(content).unwrap())
+    }
+
+    pub fn load(path: &str) -> CardNames {
+        let content = fs::read_to_string(path).unwrap();
+        serde_json::from_str(&content).unwrap()
+    }
+
+    pub fn name(&self, card: &Card) -> Result<String, NamingError> {
+        let cost_name =
+            find_name(&self.cost, card.get_cost()).ok_or(NamingError::Cost(card.get_cost()))?;
+        let atk_name = find_name(&self.attack, card.get_attack())
+            .ok_or(NamingError::Attack(card.get_attack()))?;
+        let def_name = find_name(&self.defense, card.get_defense())
+            .ok_or(NamingError::Defense(card.get_defense()))?;
+
+        let mut keywords = String::new();
+        for (has_keyword, name) in [
+            (card.get_taunt(), &self.taunt),
+            (card.get_distortion(), &self.distortion),
+            (card.get_trample(), &self.trample),
+            (card.get_first_strike(), &self.first_strike),
+        ] {
+            if has_keyword {
+                keywords += name;
+            }
+        }
+        keywords += self.on_play.get(card.get_on_play());
+        keywords += self.on_death.get(card.get_on_death());
+
+        Ok(self
+            .format
+            .replace("{cost}", cost_name)
+            .replace("{attack}", atk_name)
+            .replace("{keywords}", &keywords)
+            .replace("{defense}", def_name))
+    }
+
+    //Name used in reports, cards that cannot be named fall back to their id
+    pub fn label(&self, card: &Card) -> String {
+        self.name(card).unwrap_or_else(|_| card.get_id())
+    }
+
+    //Distinct naming errors over a pool of cards
+    pub fn check(&self, cards: &[Card]) -> Vec<NamingError> {
+        let mut errors = vec![];
+        for card in cards {
+            if let Err(error) = self.name(card) {
+                if !errors.contains(&error) {
+                    errors.push(error);
+                }
+            }
+        }
+        errors
+    }
+}
+
+fn find_name(ranges: &[NameRange], value: u32) -> Option<&str> {
+    ranges
+        .iter()
+        .find(|range| (range.min..=range.max).contains(&value))
+        .map(|range| range.name.as_str())
+}
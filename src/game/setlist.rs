@@ -1,32 +1,27 @@
-use super::card::Card;
-use rand::Rng;
+use std::collections::HashMap;
+
+use super::{
+    card::Card, card_filter::CardFilter, cost_model::CostModel, generation::GeneratorConfig,
+};
 
 pub(crate) type SetList = Box<[Card]>;
 
 pub trait SetListTrait {
-    fn gen_all() -> SetList;
-    fn get_rand(&self) -> Card;
+    fn generate(config: &GeneratorConfig, cost_model: &dyn CostModel) -> SetList;
+    fn filtered(&self, filter: &CardFilter) -> SetList;
+    fn by_id(&self) -> HashMap<String, Card>;
 }
 
 impl SetListTrait for Box<[Card]> {
-    fn gen_all() -> SetList {
+    fn generate(config: &GeneratorConfig, cost_model: &dyn CostModel) -> SetList {
         let mut set_list = vec![];
 
-        for def in 1..=16 {
-            for atk in 0..=15 {
+        for def in config.defense[0]..=config.defense[1] {
+            for atk in config.attack[0]..=config.attack[1] {
                 for prov in 0..=1 {
                     for dist in 0..=1 {
                         for trmpl in 0..=1 {
                             for f_strike in 0..=1 {
-                                if prov == 0 && atk == 0 {
-                                    continue;
-                                }
-                                if f_strike == 1 && atk == 0 {
-                                    continue;
-                                }
-                                if trmpl == 1 && atk < 2 {
-                                    continue;
-                                }
                                 let card: Card = Card::new(
                                     atk,
                                     def,
@@ -34,11 +29,11 @@ impl SetListTrait for Box<[Card]> {
                                     dist != 0,
                                     trmpl != 0,
                                     f_strike != 0,
-                                );
-                                if card.get_cost() > 8 {
-                                    break;
+                                )
+                                .priced_by(cost_model);
+                                if config.allows(&card) {
+                                    set_list.push(card);
                                 }
-                                set_list.push(card);
                             }
                         }
                     }
@@ -49,7 +44,26 @@ impl SetListTrait for Box<[Card]> {
         set_list.into_boxed_slice()
     }
 
-    fn get_rand(&self) -> Card {
-        self[rand::thread_rng().gen_range(0..self.len())]
+    fn filtered(&self, filter: &CardFilter) -> SetList {
+        self.iter()
+            .filter(|card| filter.matches(card))
+            .copied()
+            .collect()
+    }
+
+    //Ids leave the cost out, two cards of a set list cannot differ only by their cost
+    fn by_id(&self) -> HashMap<String, Card> {
+        let mut cards = HashMap::with_capacity(self.len());
+        for card in self.iter() {
+            if let Some(other) = cards.insert(card.get_id(), *card) {
+                panic!(
+                    "Cards costing {} and {} share the id {}, ids must be unique in a card pool",
+                    other.get_cost(),
+                    card.get_cost(),
+                    card.get_id()
+                );
+            }
+        }
+        cards
     }
 }
@@ -1,260 +1,758 @@
+mod balance;
+mod cli;
 mod game;
-
-use std::{fs::File, io::Write, time};
-
-use game::{deck::Deck, weighted_setlist::WeightedSetlist, Game};
-use rand::Rng;
+mod optimiser;
+mod parallel;
+
+use std::{
+    env,
+    fs::{self, File},
+    io::Write,
+    time,
+};
+
+use balance::{
+    cost_fit::{self, jitter_costs, measure_played_win_rates, print_report},
+    nash,
+    ranking::{self, export_ranking},
+    rating,
+    tournament::{self, MatchupMatrix},
+};
+use cli::{Args, Command};
+use game::{
+    card_database::CardDatabase,
+    cost_model::CostModelConfig,
+    deck::{Deck, DeckTrait},
+    deck_diff,
+    generation::GeneratorConfig,
+    naming::CardNames,
+    setlist::{SetList, SetListTrait},
+    Game,
+};
+use optimiser::{
+    checkpoint::Checkpoint,
+    coevolution::Coevolution,
+    evaluation::{AgainstOpponent, Fitness},
+    gauntlet::Gauntlet,
+    hall_of_fame::HallOfFame,
+    hill_climb::HillClimber,
+    map_elites::{Archive, MapElites},
+    neighbourhood::{candidates, evaluate_upgrades, export_upgrades},
+    pareto::{Objectives, ParetoFront},
+    proposal::Proposer,
+    run_log::{
+        deck_ids, export_steps_csv, EndRecord, Record, RunLog, RunRecord, StepRecord, SwapRecord,
+    },
+    stopping::Stopping,
+};
 fn main() {
-    let mut winrate_file = File::create("winrate.csv").unwrap();
-    winrate_file.set_len(0).unwrap();
-
-    let mut deck_cost_file = File::create("deck_cost.csv").unwrap();
-    deck_cost_file.set_len(0).unwrap();
-
-    let mut deck_atk_file = File::create("deck_atk.csv").unwrap();
-    deck_atk_file.set_len(0).unwrap();
-
-    let mut deck_def_file = File::create("deck_def.csv").unwrap();
-    deck_def_file.set_len(0).unwrap();
-
-    let mut deck_prov_file = File::create("deck_prov.csv").unwrap();
-    deck_prov_file.set_len(0).unwrap();
-
-    let mut deck_dist_file = File::create("deck_dist.csv").unwrap();
-    deck_dist_file.set_len(0).unwrap();
-
-    let mut deck_trmpl_file = File::create("deck_trmpl.csv").unwrap();
-    deck_trmpl_file.set_len(0).unwrap();
-
-    let mut deck_f_strike_file = File::create("deck_f_strike.csv").unwrap();
-    deck_f_strike_file.set_len(0).unwrap();
+    let args = Args::parse();
+
+    match args.command {
+        Command::Optimise => optimise(&args),
+        Command::FitCosts => fit_costs(&args),
+        Command::CardStats => card_stats(&args),
+        Command::RankCards => rank_cards(&args),
+        Command::ExportCards => export_cards(&args),
+        Command::Gauntlet => gauntlet(&args),
+        Command::Tournament => tournament(&args),
+        Command::Nash => nash(&args),
+        Command::MapElites => map_elites(&args),
+        Command::Upgrades => upgrades(&args),
+        Command::DiffDecks => diff_decks(&args),
+        Command::ExportRun => export_run(&args),
+    }
+}
 
+fn optimise(args: &Args) {
     let mut final_deck_a_file = File::create("deck_final_a.data").unwrap();
-    final_deck_a_file.set_len(0).unwrap();
     let mut final_deck_b_file = File::create("deck_final_b.data").unwrap();
-    final_deck_b_file.set_len(0).unwrap();
 
-    let mut game = Game::new();
+    //Without rounds only deck A is optimised, against a fixed deck B
+    let log_path = args.output.as_deref().unwrap_or("run.jsonl");
+    let (seed, n_games, mut game, gauntlet, mut run, resume_at) = match &args.resume {
+        Some(path) => {
+            let checkpoint = Checkpoint::load(path);
+            let (seed, n_games) = (checkpoint.seed, checkpoint.games);
+            //Older checkpoints continue the log where it ends
+            let resume_at = checkpoint
+                .log_length
+                .unwrap_or_else(|| fs::metadata(log_path).map_or(0, |metadata| metadata.len()));
+            let (game, gauntlet, run) = checkpoint.restore();
+            println!(
+                "Resuming {path} at round {}, side {}, iteration {}",
+                run.round, run.side, run.phase_iteration
+            );
+            (seed, n_games, game, gauntlet, run, Some(resume_at))
+        }
+        None => {
+            let seed = args.seed.unwrap_or_else(rand::random);
+            let mut game = Game::seeded(build_setlist(args), seed);
+            let sides = if args.rounds.is_some() { 2 } else { 1 };
+            let climbers = (0..sides)
+                .map(|side| {
+                    let proposer = Proposer::new(game.setlist.clone(), args.proposal, args.slots);
+                    HillClimber::new(&mut game, proposer, seed, side + 1)
+                })
+                .collect();
+            let mut run = Coevolution::new(climbers, args.rounds.unwrap_or(1), 1000);
+            //Champions are the other side's decks of earlier phases, a single climber has neither
+            if args.champions.is_some_and(|champions| champions > 0) && sides == 1 {
+                panic!(
+                    "--champions needs --rounds, without them deck B is fixed and has no champions"
+                );
+            }
+            if args.hall_of_fame.is_some() || args.champions.is_some() {
+                run.hall_of_fame = Some(HallOfFame::default());
+                run.champions = args.champions.unwrap_or(0);
+                run.seed = seed;
+            }
+            let objectives = Objectives {
+                target_turn: args.target_turn,
+                target_curve: args.target_curve.clone(),
+            };
+            if objectives
+                .target_curve
+                .as_ref()
+                .is_some_and(|curve| curve.iter().sum::<f64>() <= 0.0)
+            {
+                panic!("The target curve needs at least one positive share");
+            }
+            if !objectives.is_empty() {
+                for climber in &mut run.climbers {
+                    climber.pareto = Some(ParetoFront::new(objectives.clone()));
+                }
+            }
+            let n_games = args.games.unwrap_or(5000) as u32;
+            let gauntlet = args
+                .gauntlet
+                .as_deref()
+                .map(|path| Gauntlet::load(path, &game.setlist));
+            (seed, n_games, game, gauntlet, run, None)
+        }
+    };
+    //Co-evolved decks are scored against each other, a gauntlet would replace the other side
+    if gauntlet.is_some() && (args.rounds.is_some() || run.climbers.len() > 1) {
+        panic!("--gauntlet replaces the opponent deck and cannot be combined with --rounds");
+    }
+    if let Some(iterations) = args.iterations {
+        run.iterations = iterations;
+    }
+    if let Some(rounds) = args.rounds {
+        run.rounds = rounds;
+    }
+    //Search options given again when resuming replace the saved ones
+    for climber in &mut run.climbers {
+        climber.search.config = args.search_config(climber.search.config);
+        climber.steepest = args.steepest.or(climber.steepest);
+    }
+    //Criteria given again when resuming replace the saved ones, a larger budget lets the run go on
+    let stopping = &mut run.stopping;
+    stopping.patience = args.stopping.patience.or(stopping.patience);
+    stopping.plateau = args.stopping.plateau.or(stopping.plateau);
+    stopping.min_delta = args
+        .stopping
+        .min_delta
+        .or(stopping.min_delta)
+        .or(Some(Stopping::noise(n_games)));
+    stopping.max_time = args.stopping.max_time.or(stopping.max_time);
+    stopping.max_games = args.stopping.max_games.or(stopping.max_games);
+    run.stop = run.stopping.budget_stop(run.elapsed, run.games);
+    let fitness: &dyn Fitness = match &gauntlet {
+        Some(gauntlet) => gauntlet,
+        None => &AgainstOpponent,
+    };
+    let checkpoint_path = args.checkpoint.as_deref().unwrap_or("checkpoint.json");
+    let checkpoint_every = args.checkpoint_every.unwrap_or(10);
+    let names = build_names(args, &game.setlist);
+    let mut run_log = RunLog::open(log_path, resume_at);
+    run_log.write(&Record::Run(RunRecord {
+        seed,
+        games: n_games,
+        iterations: run.iterations,
+        rounds: run.rounds,
+        resumed: resume_at.is_some(),
+        arguments: env::args().collect(),
+        setlist: RunRecord::setlist(&game.setlist),
+    }));
 
     let start = time::Instant::now();
-
-    let mut previous_total_wins = 0;
-    let mut previous_deck = game.p1.base_deck.clone();
+    let elapsed = run.elapsed;
 
     println!("{}", game.setlist.len());
+    println!("Seed : {seed}");
+
+    let mut n_steps = 0;
+    while !run.is_finished() {
+        let (round, side, iteration) = (run.round, run.side, run.phase_iteration);
+        let n_phases = run.phases.len();
+        run.elapsed = elapsed + start.elapsed().as_secs_f64();
+        let step = run.step(&mut game, fitness, n_games);
+        n_steps += 1;
+
+        let climber = &run.climbers[side];
+        run_log.write(&Record::Step(StepRecord {
+            round,
+            side,
+            iteration,
+            score: step.score,
+            win_rate: climber.best_score,
+            avg_turn: step.avg_turn,
+            accepted: step.accepted,
+            games: step.games,
+            deck: deck_ids(&climber.best_deck),
+            swaps: climber.last_swaps.iter().map(SwapRecord::from).collect(),
+        }));
+
+        if let Some(upgrade) = run.climbers[side].upgrades.first() {
+            println!(
+                "Iteration {iteration}: best of {} swaps {} -> {} ({:+.4}){}",
+                run.climbers[side].upgrades.len(),
+                names.label(&upgrade.outgoing),
+                names.label(&upgrade.incoming),
+                upgrade.gain,
+                if step.accepted { "" } else { ", kept the deck" }
+            );
+        }
 
-    for _ in 0..1 {
-        let mut weighted_setlist = WeightedSetlist::new(game.setlist.clone(), 100);
-        let mut change_card;
-        let mut index_changed = 0;
-        let mut last_index_changed = 0;
-        for _ in 0..1000 {
-            let n_games: u32 = 5000;
-
-            let (total_wins1, total_turns1, _) = play_games(&mut game, n_games as usize);
-            //switch player
-            let c = game.p1;
-            game.p1 = game.p2;
-            game.p2 = c;
-
-            let (total_wins2, total_turns2, _) = play_games(&mut game, n_games as usize);
-
-            //switch player back
-            let c = game.p1;
-            game.p1 = game.p2;
-            game.p2 = c;
-
-            let total_wins = total_wins1 + n_games - total_wins2;
-            let avg_turn = (total_turns1 + total_turns2) as f32 / (n_games as f32 * 2.0);
-
-            if previous_total_wins > total_wins {
-                log_stats(
-                    &mut winrate_file,
-                    &mut deck_cost_file,
-                    &mut deck_atk_file,
-                    &mut deck_def_file,
-                    &mut deck_prov_file,
-                    &mut deck_dist_file,
-                    &mut deck_trmpl_file,
-                    &mut deck_f_strike_file,
-                    &previous_deck,
-                    &previous_total_wins,
-                    &avg_turn,
-                );
-
-                //Update deck
-                game.p1.base_deck = previous_deck.to_owned();
-                let len = game.p1.base_deck.len();
+        if let Some(phase) = run.phases.get(n_phases) {
+            run_log.write(&Record::Phase(*phase));
+            println!(
+                "Round {} deck {}: win rate {:.4} -> {:.4}, {} swaps accepted in {} iterations ({:?})",
+                phase.round,
+                ["A", "B"][phase.side],
+                phase.start_win_rate,
+                phase.end_win_rate,
+                phase.accepted,
+                phase.iterations,
+                phase.stop
+            );
+            //The next phase of the side starts a new front against the new opponent
+            if let (Some(pareto), true) = (&run.climbers[phase.side].pareto, run.climbers.len() > 1)
+            {
+                save_pareto(pareto, phase.side, Some(phase.round));
+            }
+        }
 
-                weighted_setlist.change_weight(index_changed, 1);
-                weighted_setlist.change_weight(last_index_changed, -1);
+        if n_steps % checkpoint_every == 0 || run.is_finished() {
+            Checkpoint::new(seed, n_games, &game, gauntlet.as_ref(), &run, run_log.len())
+                .save(checkpoint_path);
+            if let (Some(hall_of_fame), Some(path)) = (&run.hall_of_fame, &args.hall_of_fame) {
+                hall_of_fame.save(path);
+            }
+        }
+    }
 
-                (change_card, last_index_changed) = weighted_setlist.get_rand();
-                while game
-                    .p1
-                    .base_deck
-                    .iter()
-                    .filter(|card| **card == change_card)
-                    .count()
-                    > 2
-                {
-                    (change_card, last_index_changed) = weighted_setlist.get_rand();
-                }
+    let end = time::Instant::now();
+    println!("Elapsed : {:?}", end - start);
+    run.elapsed = elapsed + (end - start).as_secs_f64();
+    let summary = run.summary(seed);
+    println!(
+        "Stopped: {:?} after {} steps and {} games",
+        summary.stop, summary.steps, summary.games
+    );
+    //p1 holds the deck of the side optimised next, deck B when a budget ended deck A's phase
+    let (deck_a, deck_b) = match run.side {
+        0 => (game.p1.base_deck, game.p2.base_deck),
+        _ => (game.p2.base_deck, game.p1.base_deck),
+    };
+    run_log.write(&Record::End(EndRecord {
+        summary,
+        deck_a: deck_ids(&deck_a),
+        deck_b: deck_ids(&deck_b),
+    }));
+
+    deck_a.save(&mut final_deck_a_file);
+    deck_b.save(&mut final_deck_b_file);
+
+    for (side, climber) in run.climbers.iter().enumerate() {
+        println!(
+            "Deck {}: win rate {:.4}, {} restarts, {} tabu swaps drawn again",
+            ["A", "B"][side],
+            climber.best_score,
+            climber.search.restarts,
+            climber.search.tabu_hits
+        );
+        println!("Deck {} proposal weights", ["A", "B"][side]);
+        for (card, weight) in climber.proposer.card_weights().iter().take(10) {
+            println!("{:.4} {}", weight, names.label(card));
+        }
+        let path = ["proposal_weights.csv", "proposal_weights_b.csv"][side];
+        let mut weights_file = File::create(path).unwrap();
+        climber.proposer.export_weights(&names, &mut weights_file);
 
-                index_changed = rand::thread_rng().gen_range(0..len);
-                game.p1.base_deck[index_changed] = change_card;
-            } else {
-                log_stats(
-                    &mut winrate_file,
-                    &mut deck_cost_file,
-                    &mut deck_atk_file,
-                    &mut deck_def_file,
-                    &mut deck_prov_file,
-                    &mut deck_dist_file,
-                    &mut deck_trmpl_file,
-                    &mut deck_f_strike_file,
-                    &previous_deck,
-                    &previous_total_wins,
-                    &avg_turn,
-                );
+        if !climber.upgrades.is_empty() {
+            let path = ["upgrades.csv", "upgrades_b.csv"][side];
+            export_upgrades(&climber.upgrades, &names, &mut File::create(path).unwrap());
+        }
 
-                //Update deck
-                previous_total_wins = total_wins;
-                previous_deck = game.p1.base_deck.to_owned();
-                let len = previous_deck.len();
+        if let Some(pareto) = &climber.pareto {
+            println!(
+                "Deck {} Pareto front: {} decks",
+                ["A", "B"][side],
+                pareto.decks.len()
+            );
+            save_pareto(pareto, side, None);
+        }
+    }
+}
 
-                weighted_setlist.change_weight(index_changed, -1);
-                weighted_setlist.change_weight(last_index_changed, 1);
+//pareto_front.json and .csv for deck A, pareto_front_b for deck B, _r<round> for a single round
+fn save_pareto(pareto: &ParetoFront, side: usize, round: Option<usize>) {
+    let path = format!(
+        "pareto_front{}{}",
+        ["", "_b"][side],
+        round.map_or(String::new(), |round| format!("_r{round}"))
+    );
+    pareto.save(&format!("{path}.json"));
+    pareto.export_csv(&mut File::create(format!("{path}.csv")).unwrap());
+}
 
-                (change_card, last_index_changed) = weighted_setlist.get_rand();
-                while game
-                    .p1
-                    .base_deck
-                    .iter()
-                    .filter(|card| **card == change_card)
-                    .count()
-                    > 2
-                {
-                    (change_card, last_index_changed) = weighted_setlist.get_rand();
-                }
+fn build_game(args: &Args) -> Game {
+    match args.seed {
+        Some(seed) => Game::seeded(build_setlist(args), seed),
+        None => Game::with_setlist(build_setlist(args)),
+    }
+}
 
-                index_changed = rand::thread_rng().gen_range(0..len);
-                game.p1.base_deck[index_changed] = change_card;
-            }
+//Cards come from the card database if one is given, otherwise they are generated
+fn build_setlist(args: &Args) -> SetList {
+    let cost_model = args
+        .cost_model
+        .as_deref()
+        .map_or(CostModelConfig::Default, CostModelConfig::load)
+        .build();
+
+    let setlist = match &args.cards {
+        Some(path) => CardDatabase::load(path, cost_model.as_ref()).to_setlist(),
+        None => {
+            let generator = args
+                .generator
+                .as_deref()
+                .map_or_else(GeneratorConfig::default, GeneratorConfig::load);
+            SetList::generate(&generator, cost_model.as_ref())
         }
+    }
+    .filtered(&args.filter);
 
-        //Switch player optimisation
-        game.switch_player();
+    if setlist.is_empty() {
+        panic!("No card left in the pool after filtering");
+    }
+    setlist
+}
 
-        previous_total_wins = 0;
-        previous_deck = game.p1.base_deck.clone();
+//Naming problems are reported once, reports then fall back to card ids
+fn build_names(args: &Args, setlist: &SetList) -> CardNames {
+    let names = match &args.names {
+        Some(path) => CardNames::load(path),
+        None => CardNames::locale(args.locale.as_deref().unwrap_or("fr"))
+            .unwrap_or_else(|| panic!("Unknown locale {}", args.locale.as_deref().unwrap_or(""))),
+    };
 
-        let end = time::Instant::now();
-        println!("Elapsed : {:?}", end - start);
+    for error in names.check(setlist) {
+        eprintln!("Warning: {error}, the card id is used instead");
     }
+    names
+}
 
-    final_deck_a_file
-        .write_all(
-            format!(
-                "{{\n\t\"Cards\": {}}}",
-                serde_json::to_string_pretty(&game.p1.deck).unwrap()
-            )
-            .as_bytes(),
-        )
-        .unwrap();
-    final_deck_b_file
-        .write_all(
-            format!(
-                "{{\n\t\"Cards\": {}}}",
-                serde_json::to_string_pretty(&game.p2.deck).unwrap()
-            )
-            .as_bytes(),
-        )
-        .unwrap();
+fn export_cards(args: &Args) {
+    let database = CardDatabase::from_setlist(&build_setlist(args));
+    let path = args.output.as_deref().unwrap_or("cards.json");
+    database.save(path);
+    println!("{} cards written to {path}", database.len());
+}
+
+fn card_stats(args: &Args) {
+    let mut game = build_game(args);
+    if let Some(path) = &args.deck {
+        game.p1.base_deck = Deck::load(path, &game.setlist);
+    }
+    if let Some(path) = &args.opponent {
+        game.p2.base_deck = Deck::load(path, &game.setlist);
+    }
+    game.track_card_stats = true;
+    let names = build_names(args, &game.setlist);
+
+    //The studied deck plays both first and second
+    let n_games = args.games.unwrap_or(5000);
+    let (_, _, [mut card_stats, _]) = game.play_games(n_games);
+    std::mem::swap(&mut game.p1, &mut game.p2);
+    let (_, _, [_, second_player_stats]) = game.play_games(n_games);
+    card_stats.merge(&second_player_stats);
+
+    let mut card_stats_file =
+        File::create(args.output.as_deref().unwrap_or("card_stats.csv")).unwrap();
+    card_stats.export_csv(&names, &mut card_stats_file);
 }
 
-fn play_games(game: &mut Game, n: usize) -> (u32, u32, [u32; 30]) {
-    let mut player1_win_count = 0;
-    let mut total_turns = 0;
-    let mut deck_cost = [0; 30];
-    for _ in 0..n {
-        let s = game.play();
-        player1_win_count += s.player1_won as u32;
-        total_turns += s.nb_turns;
-        deck_cost = s.deck_cost;
+fn gauntlet(args: &Args) {
+    let mut game = build_game(args);
+    if let Some(path) = &args.deck {
+        game.p1.base_deck = Deck::load(path, &game.setlist);
+    }
+    let gauntlet = Gauntlet::load(
+        args.gauntlet
+            .as_deref()
+            .expect("The gauntlet command needs --gauntlet"),
+        &game.setlist,
+    );
+
+    let evaluation = gauntlet.evaluate(&mut game, args.games.unwrap_or(5000) as u32);
+    for matchup in &evaluation.matchups {
+        println!(
+            "{} (weight {}): {:.4} over {} games, {:.1} turns",
+            matchup.name,
+            matchup.weight,
+            matchup.win_rate(),
+            matchup.games,
+            matchup.avg_turn
+        );
     }
+    println!("Weighted win rate: {:.4}", evaluation.score);
 
-    (player1_win_count, total_turns, deck_cost)
+    let mut gauntlet_file = File::create(args.output.as_deref().unwrap_or("gauntlet.csv")).unwrap();
+    Gauntlet::export_matchups(&evaluation, &mut gauntlet_file);
 }
 
-fn log_stats(
-    winrate_file: &mut File,
-    deck_cost_file: &mut File,
-    deck_atk_file: &mut File,
-    deck_def_file: &mut File,
-    deck_prov_file: &mut File,
-    deck_dist_file: &mut File,
-    deck_trmpl_file: &mut File,
-    deck_f_strike_file: &mut File,
-    deck: &Deck,
-    win_amnt: &u32,
-    avg_turn: &f32,
-) {
-    winrate_file
-        .write_all(format!("{},{}\n", win_amnt, avg_turn).as_bytes())
-        .unwrap();
+//Spreadsheet view of the run output of optimise, one row per step
+fn export_run(args: &Args) {
+    let records = RunLog::load(args.run.as_deref().unwrap_or("run.jsonl"));
+    let path = args.output.as_deref().unwrap_or("steps.csv");
+    export_steps_csv(&records, &mut File::create(path).unwrap());
+}
 
-    let mut arr = Vec::with_capacity(30);
-    for card in deck {
-        arr.push(card.get_cost())
+//Card level differences between the two decks of --decks, deck or gauntlet files
+fn diff_decks(args: &Args) {
+    let gauntlet = Gauntlet::load(
+        args.decks
+            .as_deref()
+            .expect("The diff-decks command needs --decks a,b"),
+        &build_setlist(args),
+    );
+    let [a, b] = &gauntlet.decks[..] else {
+        panic!(
+            "The diff-decks command compares 2 decks, {} were given",
+            gauntlet.decks.len()
+        );
+    };
+    let (a_deck, b_deck) = (a.deck(), b.deck());
+    let cards: SetList = a_deck.iter().chain(&b_deck).copied().collect();
+    let names = build_names(args, &cards);
+
+    let differences = deck_diff::diff(&a_deck, &b_deck);
+    println!("{} -> {}", a.name, b.name);
+    for difference in &differences {
+        let label = names.label(&difference.card);
+        match difference.difference() {
+            0 => println!("   {} {label}", difference.count_a),
+            change => println!(
+                "{change:+} {label} ({} -> {})",
+                difference.count_a, difference.count_b
+            ),
+        }
     }
-    write_arr_in_file(arr.as_slice(), deck_cost_file);
+    let changed: i64 = differences
+        .iter()
+        .map(|difference| difference.difference().max(0))
+        .sum();
+    let average_cost = |deck: &Deck| {
+        deck.iter().map(|card| card.get_cost()).sum::<u32>() as f64 / deck.len() as f64
+    };
+    println!(
+        "{changed} cards swapped, average cost {:.2} -> {:.2}",
+        average_cost(&a_deck),
+        average_cost(&b_deck)
+    );
+
+    let mut diff_file = File::create(args.output.as_deref().unwrap_or("deck_diff.csv")).unwrap();
+    deck_diff::export_csv(&differences, &names, &mut diff_file);
+}
 
-    let mut arr = Vec::with_capacity(30);
-    for card in deck {
-        arr.push(card.get_attack())
+//Ranks the single card swaps of --deck against the gauntlet or the opponent, every distinct card of
+//the deck against a sample of --samples setlist cards
+fn upgrades(args: &Args) {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut game = Game::seeded(build_setlist(args), seed);
+    if let Some(path) = &args.deck {
+        game.p1.base_deck = Deck::load(path, &game.setlist);
+    }
+    if let Some(path) = &args.opponent {
+        game.p2.base_deck = Deck::load(path, &game.setlist);
     }
-    write_arr_in_file(arr.as_slice(), deck_atk_file);
+    let gauntlet = args
+        .gauntlet
+        .as_deref()
+        .map(|path| Gauntlet::load(path, &game.setlist));
+    let fitness: &dyn Fitness = match &gauntlet {
+        Some(gauntlet) => gauntlet,
+        None => &AgainstOpponent,
+    };
+    let names = build_names(args, &game.setlist);
+
+    let swaps = candidates(
+        &game.p1.base_deck,
+        &game.setlist,
+        args.samples.unwrap_or(50),
+        &mut game.rng,
+    );
+    let (evaluation, upgrades) = evaluate_upgrades(
+        &game,
+        fitness,
+        args.games.unwrap_or(1000) as u32,
+        &swaps,
+        seed,
+    );
+    println!("Deck win rate: {:.4}", evaluation.score);
+    for upgrade in upgrades.iter().take(20) {
+        println!(
+            "{:+.4} slot {}: {} -> {}",
+            upgrade.gain,
+            upgrade.slot,
+            names.label(&upgrade.outgoing),
+            names.label(&upgrade.incoming)
+        );
+    }
+
+    let mut upgrades_file = File::create(args.output.as_deref().unwrap_or("upgrades.csv")).unwrap();
+    export_upgrades(&upgrades, &names, &mut upgrades_file);
+}
 
-    let mut arr = Vec::with_capacity(30);
-    for card in deck {
-        arr.push(card.get_defense())
+//Fills a grid of behaviours (--axes) with the best deck of each cell. p1 plays every candidate
+//against the gauntlet or the opponent, --random-decks random decks start the archive.
+fn map_elites(args: &Args) {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut game = Game::seeded(build_setlist(args), seed);
+    if let Some(path) = &args.opponent {
+        game.p2.base_deck = Deck::load(path, &game.setlist);
+    }
+    let gauntlet = args
+        .gauntlet
+        .as_deref()
+        .map(|path| Gauntlet::load(path, &game.setlist));
+    let fitness: &dyn Fitness = match &gauntlet {
+        Some(gauntlet) => gauntlet,
+        None => &AgainstOpponent,
+    };
+    let n_games = args.games.unwrap_or(5000) as u32;
+    let iterations = args.iterations.unwrap_or(1000);
+
+    let axes = if args.axes.is_empty() {
+        cli::parse_axes("--axes", Some("avg-cost:2:10:8,taunt:0:1:5".to_owned()))
+    } else {
+        args.axes.clone()
+    };
+    let proposer = Proposer::new(game.setlist.clone(), args.proposal, args.slots);
+    let mut search = MapElites::new(
+        Archive::new(axes),
+        proposer,
+        args.random_decks.unwrap_or(50),
+        seed,
+    );
+
+    println!("Seed : {seed}");
+    while search.iteration < iterations {
+        search.step(&mut game, fitness, n_games);
+        if search.iteration.is_multiple_of(100) || search.iteration == iterations {
+            println!(
+                "Iteration {}: {}/{} cells, QD score {:.2}",
+                search.iteration,
+                search.archive.decks.len(),
+                search.archive.n_cells(),
+                search
+                    .archive
+                    .decks
+                    .iter()
+                    .map(|elite| elite.score)
+                    .sum::<f64>()
+            );
+        }
     }
-    write_arr_in_file(arr.as_slice(), deck_def_file);
 
-    let mut arr = Vec::with_capacity(30);
-    for card in deck {
-        arr.push(card.get_taunt() as u32)
+    print_archive(&search.archive);
+    let path = args.output.as_deref().unwrap_or("map_elites.json");
+    search.archive.save(path);
+    let mut archive_file = File::create("map_elites.csv").unwrap();
+    search.archive.export_csv(&mut archive_file);
+}
+
+//Win rate of each elite, the first axis in columns and the second in rows
+fn print_archive(archive: &Archive) {
+    let (columns, rows) = match &archive.axes[..] {
+        [column] => (column, None),
+        [column, row] => (column, Some(row)),
+        _ => return,
+    };
+
+    let header: Vec<String> = (0..columns.bins)
+        .map(|bin| format!("{:>6.2}", columns.bin_start(bin)))
+        .collect();
+    println!(
+        "{:>8} {}",
+        rows.map_or(columns.name.clone(), |row| format!(
+            "{}/{}",
+            row.name, columns.name
+        )),
+        header.join(" ")
+    );
+    for row_bin in 0..rows.map_or(1, |row| row.bins) {
+        let line: Vec<String> = (0..columns.bins)
+            .map(|bin| {
+                let cell = match rows {
+                    Some(_) => vec![bin, row_bin],
+                    None => vec![bin],
+                };
+                archive.get(&cell).map_or(format!("{:>6}", "-"), |elite| {
+                    format!("{:>6.3}", elite.score)
+                })
+            })
+            .collect();
+        let label = rows.map_or(String::new(), |row| {
+            format!("{:.2}", row.bin_start(row_bin))
+        });
+        println!("{label:>8} {}", line.join(" "));
     }
-    write_arr_in_file(arr.as_slice(), deck_prov_file);
+}
 
-    let mut arr = Vec::with_capacity(30);
-    for card in deck {
-        arr.push(card.get_distortion() as u32)
+//Decks come from deck or gauntlet files (--decks) and from the setlist (--random-decks)
+fn tournament(args: &Args) {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut game = Game::seeded(build_setlist(args), seed);
+
+    let mut decks: Vec<(String, Deck)> = args
+        .decks
+        .as_deref()
+        .map(|paths| Gauntlet::load(paths, &game.setlist))
+        .unwrap_or_default()
+        .decks
+        .iter()
+        .map(|deck| (deck.name.clone(), deck.deck()))
+        .collect();
+    for i in 0..args.random_decks.unwrap_or(0) {
+        decks.push((
+            format!("Random{}", i + 1),
+            Deck::new(&game.setlist, &mut game.rng),
+        ));
+    }
+    if decks.len() < 2 {
+        panic!("A tournament needs at least 2 decks, use --decks or --random-decks");
     }
-    write_arr_in_file(arr.as_slice(), deck_dist_file);
 
-    let mut arr = Vec::with_capacity(30);
-    for card in deck {
-        arr.push(card.get_trample() as u32)
+    let matrix = tournament::round_robin(
+        &game.setlist,
+        &decks,
+        args.games.unwrap_or(1000) as u32,
+        seed,
+    );
+    let mut ratings = rating::elo_ratings(&matrix);
+    ratings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+    for rating in &ratings {
+        println!(
+            "{:>7.1} ± {:>5.1} {}",
+            rating.elo,
+            1.96 * rating.deviation,
+            rating.name
+        );
     }
-    write_arr_in_file(arr.as_slice(), deck_trmpl_file);
 
-    let mut arr = Vec::with_capacity(30);
-    for card in deck {
-        arr.push(card.get_first_strike() as u32)
+    let mut matrix_file = File::create(args.output.as_deref().unwrap_or("matchups.csv")).unwrap();
+    matrix.export_csv(&mut matrix_file);
+    let mut ratings_file = File::create("ratings.csv").unwrap();
+    rating::export_ratings(&ratings, &mut ratings_file);
+}
+
+//Reads the matchup matrix written by the tournament command
+fn nash(args: &Args) {
+    let matrix = MatchupMatrix::load_csv(args.matchups.as_deref().unwrap_or("matchups.csv"));
+    let equilibrium = nash::solve(&matrix);
+
+    let mut order: Vec<usize> = (0..matrix.len()).collect();
+    order.sort_by(|a, b| equilibrium.mixture[*b].total_cmp(&equilibrium.mixture[*a]));
+    for deck in order {
+        println!(
+            "{:.4} {} (win rate vs mixture {:.4}, exploitable by {:+.4} with {})",
+            equilibrium.mixture[deck],
+            matrix.names[deck],
+            equilibrium.win_rate_vs_mixture[deck],
+            equilibrium.exploitability[deck],
+            matrix.names[equilibrium.best_response[deck]]
+        );
     }
-    write_arr_in_file(arr.as_slice(), deck_f_strike_file);
+    println!(
+        "Effective number of decks: {:.2}, exploitability of the mixture: {:.6}",
+        equilibrium.effective_decks(),
+        equilibrium.mixture_exploitability
+    );
+
+    let mut nash_file = File::create(args.output.as_deref().unwrap_or("nash.csv")).unwrap();
+    nash::export_equilibrium(&matrix, &equilibrium, &mut nash_file);
 }
 
-fn write_arr_in_file(values: &[u32], file: &mut File) {
-    for i in 0..values.len() {
-        if i < values.len() - 1 {
-            file.write_all(format!("{:?}, ", values[i]).as_bytes())
-                .unwrap()
-        } else {
-            file.write_all(format!("{:?}\n", values[i]).as_bytes())
-                .unwrap()
+fn rank_cards(args: &Args) {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let setlist = build_setlist(args);
+    let names = build_names(args, &setlist);
+    println!("Seed : {seed}");
+    let ranks = ranking::rank_cards(
+        &setlist,
+        args.baselines.unwrap_or(8),
+        args.games.unwrap_or(100),
+        seed,
+    );
+
+    //Outliers of each cost
+    let max_cost = ranks
+        .iter()
+        .map(|rank| rank.card.get_cost())
+        .max()
+        .unwrap_or(0);
+    for cost in 1..=max_cost {
+        let same_cost = ranks.iter().filter(|rank| rank.card.get_cost() == cost);
+        let strongest = same_cost
+            .clone()
+            .max_by(|a, b| a.delta_vs_cost.total_cmp(&b.delta_vs_cost));
+        let weakest = same_cost.min_by(|a, b| a.delta_vs_cost.total_cmp(&b.delta_vs_cost));
+        if let (Some(strongest), Some(weakest)) = (strongest, weakest) {
+            println!(
+                "Cost {cost}: strongest {} ({:+.4}), weakest {} ({:+.4})",
+                names.label(&strongest.card),
+                strongest.delta_vs_cost,
+                names.label(&weakest.card),
+                weakest.delta_vs_cost
+            );
         }
     }
+
+    let mut ranking_file =
+        File::create(args.output.as_deref().unwrap_or("card_ranking.csv")).unwrap();
+    export_ranking(&ranks, &names, &mut ranking_file);
+}
+
+fn fit_costs(args: &Args) {
+    let cost_model = args
+        .cost_model
+        .as_deref()
+        .map_or(CostModelConfig::Default, CostModelConfig::load);
+
+    //Costs are jittered so that the regression can separate the effect of mana from the stats
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut game = Game::seeded(build_setlist(args), seed);
+    game.setlist = jitter_costs(&game.setlist, &mut game.rng);
+    println!("Seed : {seed}");
+
+    let records = measure_played_win_rates(&mut game, args.games.unwrap_or(200_000));
+    let Some(fit) = cost_fit::fit_costs(&records, &cost_model.weights()) else {
+        println!("No card was played, costs were not fitted");
+        return;
+    };
+    print_report(&fit);
+
+    let mut fitted_file =
+        File::create(args.output.as_deref().unwrap_or("fitted_cost_model.json")).unwrap();
+    fitted_file
+        .write_all(
+            serde_json::to_string_pretty(&CostModelConfig::Weighted(fit.fitted))
+                .unwrap()
+                .as_bytes(),
+        )
+        .unwrap();
 }
@@ -0,0 +1,144 @@
+use std::fs;
+
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    coevolution::{Coevolution, CoevolutionState},
+    gauntlet::Gauntlet,
+};
+use crate::game::{card::Card, deck::Deck, Game};
+
+//Snapshot of an optimisation run, the setlist is stored so that resuming does not depend on the
+//card options given again
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct Checkpoint {
+    pub seed: u64,
+    pub games: u32,
+    pub setlist: Vec<Card>,
+    pub p1_deck: Deck,
+    pub p2_deck: Deck,
+    pub game_rng: ChaCha8Rng,
+    //Opponents of the fitness, p2's base deck when there is none
+    pub gauntlet: Option<Gauntlet>,
+    pub run: CoevolutionState,
+    //Length in bytes of the run log when the checkpoint was saved, a resumed run cuts the log back
+    //to it. None for checkpoints written before the log was cut.
+    #[serde(default)]
+    pub log_length: Option<u64>,
+}
+
+impl Checkpoint {
+    pub fn new(
+        seed: u64,
+        games: u32,
+        game: &Game,
+        gauntlet: Option<&Gauntlet>,
+        run: &Coevolution,
+        log_length: u64,
+    ) -> Checkpoint {
+        Checkpoint {
+            seed,
+            games,
+            setlist: game.setlist.to_vec(),
+            p1_deck: game.p1.base_deck,
+            p2_deck: game.p2.base_deck,
+            game_rng: game.rng.clone(),
+            gauntlet: gauntlet.cloned(),
+            run: run.state(),
+            log_length: Some(log_length),
+        }
+    }
+
+    pub fn load(path: &str) -> Checkpoint {
+        let content = fs::read_to_string(path).unwrap();
+        serde_json::from_str(&content).unwrap()
+    }
+
+    //Written next to the previous checkpoint first so that an interruption never leaves a
+    //truncated file
+    pub fn save(&self, path: &str) {
+        let temporary = format!("{path}.tmp");
+        fs::write(&temporary, serde_json::to_string(self).unwrap()).unwrap();
+        fs::rename(&temporary, path).unwrap();
+    }
+
+    pub fn restore(self) -> (Game, Option<Gauntlet>, Coevolution) {
+        let setlist = self.setlist.into_boxed_slice();
+        let mut game = Game::seeded(setlist.clone(), self.seed);
+        game.p1.base_deck = self.p1_deck;
+        game.p2.base_deck = self.p2_deck;
+        game.rng = self.game_rng;
+
+        let run = Coevolution::restore(setlist, self.run);
+        game.track_card_stats = run.climber().proposer.needs_card_stats();
+        (game, self.gauntlet, run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::setlist::SetList,
+        optimiser::{
+            evaluation::AgainstOpponent,
+            hill_climb::HillClimber,
+            proposal::{CardProposal, Proposer, SlotProposal},
+            search::Restart,
+        },
+    };
+
+    const SEED: u64 = 37;
+    const GAMES: u32 = 20;
+
+    //Without taunt every card attacks the opponent once, any deck deals more than its 100 health
+    //so that games end
+    fn start() -> (Game, Coevolution) {
+        let setlist: SetList = (0..40)
+            .map(|i| Card::new(i % 5 + 4, i / 5 + 1, false, i % 3 == 0, i % 4 == 0, false))
+            .collect();
+        let mut game = Game::seeded(setlist, SEED);
+        let climbers = (0..2)
+            .map(|side| {
+                let proposer = Proposer::new(
+                    game.setlist.clone(),
+                    CardProposal::Exp3,
+                    SlotProposal::Contribution,
+                );
+                let mut climber = HillClimber::new(&mut game, proposer, SEED, side + 1);
+                climber.search.config.tabu_tenure = 5;
+                climber.search.config.restart = Restart::Iterated;
+                climber.search.config.restart_after = 4;
+                climber
+            })
+            .collect();
+        (game, Coevolution::new(climbers, 2, 8))
+    }
+
+    fn finish(game: &mut Game, run: &mut Coevolution) -> (Deck, Deck) {
+        while !run.is_finished() {
+            run.step(game, &AgainstOpponent, GAMES);
+        }
+        (run.climbers[0].best_deck, run.climbers[1].best_deck)
+    }
+
+    //A run saved to a checkpoint in the middle of a phase and resumed from it ends with the decks
+    //of the run that was never interrupted
+    #[test]
+    fn resumed_run_ends_like_an_uninterrupted_one() {
+        let (mut game, mut run) = start();
+        let uninterrupted = finish(&mut game, &mut run);
+
+        let (mut game, mut run) = start();
+        for _ in 0..13 {
+            run.step(&mut game, &AgainstOpponent, GAMES);
+        }
+        let saved =
+            serde_json::to_string(&Checkpoint::new(SEED, GAMES, &game, None, &run, 0)).unwrap();
+        let checkpoint: Checkpoint = serde_json::from_str(&saved).unwrap();
+        let (mut game, _, mut run) = checkpoint.restore();
+        assert_eq!(finish(&mut game, &mut run), uninterrupted);
+    }
+}
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    evaluation::Fitness,
+    hall_of_fame::{HallOfFame, WithChampions},
+    hill_climb::{ClimberState, HillClimber, Step},
+    stopping::{StopReason, Stopping},
+};
+use crate::game::{setlist::SetList, Game};
+
+//Win rates of a side at the start and at the end of one of its phases
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "PascalCase")]
+pub struct PhaseResult {
+    pub round: usize,
+    pub side: usize,
+    pub start_win_rate: f64,
+    pub end_win_rate: f64,
+    pub accepted: usize,
+    #[serde(default)]
+    pub iterations: usize,
+    #[serde(default)]
+    pub stop: StopReason,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct CoevolutionState {
+    pub rounds: usize,
+    pub iterations: usize,
+    pub round: usize,
+    pub side: usize,
+    pub phase_iteration: usize,
+    pub climbers: Vec<ClimberState>,
+    pub phases: Vec<PhaseResult>,
+    pub hall_of_fame: Option<HallOfFame>,
+    pub champions: usize,
+    pub seed: u64,
+    #[serde(default)]
+    pub stopping: Stopping,
+    #[serde(default)]
+    pub games: u64,
+    #[serde(default)]
+    pub elapsed: f64,
+    #[serde(default)]
+    pub stop: Option<StopReason>,
+}
+
+//Outcome of a run, written when it ends
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct RunSummary {
+    pub seed: u64,
+    pub stop: StopReason,
+    pub steps: usize,
+    pub games: u64,
+    pub elapsed: f64,
+    pub phases: Vec<PhaseResult>,
+}
+
+//Each round optimises every side in turn for a number of iterations against the current deck of
+//the other side. Side 0 is deck A and side 1 deck B, the side being optimised is always p1.
+//With a single climber only deck A is optimised, against a fixed deck B.
+//The hall of fame records every accepted deck. When champions is not 0 the candidates of a phase
+//also face a sample of the other side's champions of the previous phases.
+//A phase ends early when it stops improving, a budget of time or games ends the whole run.
+pub struct Coevolution {
+    pub rounds: usize,
+    pub iterations: usize,
+    pub round: usize,
+    pub side: usize,
+    pub phase_iteration: usize,
+    pub climbers: Vec<HillClimber>,
+    pub phases: Vec<PhaseResult>,
+    pub hall_of_fame: Option<HallOfFame>,
+    pub champions: usize,
+    pub seed: u64,
+    pub stopping: Stopping,
+    pub games: u64,
+    //Seconds spent running, across resumes
+    pub elapsed: f64,
+    pub stop: Option<StopReason>,
+}
+
+impl Coevolution {
+    pub fn new(climbers: Vec<HillClimber>, rounds: usize, iterations: usize) -> Coevolution {
+        Coevolution {
+            rounds,
+            iterations,
+            round: 0,
+            side: 0,
+            phase_iteration: 0,
+            climbers,
+            phases: vec![],
+            hall_of_fame: None,
+            champions: 0,
+            seed: 0,
+            stopping: Stopping::default(),
+            games: 0,
+            elapsed: 0.0,
+            stop: None,
+        }
+    }
+
+    pub fn restore(setlist: SetList, state: CoevolutionState) -> Coevolution {
+        Coevolution {
+            rounds: state.rounds,
+            iterations: state.iterations,
+            round: state.round,
+            side: state.side,
+            phase_iteration: state.phase_iteration,
+            climbers: state
+                .climbers
+                .into_iter()
+                .map(|climber| HillClimber::restore(setlist.clone(), climber))
+                .collect(),
+            phases: state.phases,
+            hall_of_fame: state.hall_of_fame,
+            champions: state.champions,
+            seed: state.seed,
+            stopping: state.stopping,
+            games: state.games,
+            elapsed: state.elapsed,
+            stop: state.stop,
+        }
+    }
+
+    pub fn state(&self) -> CoevolutionState {
+        CoevolutionState {
+            rounds: self.rounds,
+            iterations: self.iterations,
+            round: self.round,
+            side: self.side,
+            phase_iteration: self.phase_iteration,
+            climbers: self.climbers.iter().map(HillClimber::state).collect(),
+            phases: self.phases.clone(),
+            hall_of_fame: self.hall_of_fame.clone(),
+            champions: self.champions,
+            seed: self.seed,
+            stopping: self.stopping,
+            games: self.games,
+            elapsed: self.elapsed,
+            stop: self.stop,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stop.is_some() || self.round >= self.rounds
+    }
+
+    pub fn summary(&self, seed: u64) -> RunSummary {
+        RunSummary {
+            seed,
+            stop: self.stop_reason(),
+            steps: self
+                .climbers
+                .iter()
+                .map(|climber| climber.history.len())
+                .sum(),
+            games: self.games,
+            elapsed: self.elapsed,
+            phases: self.phases.clone(),
+        }
+    }
+
+    //Why the run ended, or why its last phase did
+    pub fn stop_reason(&self) -> StopReason {
+        self.stop
+            .or(self.phases.last().map(|phase| phase.stop))
+            .unwrap_or_default()
+    }
+
+    pub fn climber(&self) -> &HillClimber {
+        &self.climbers[self.side]
+    }
+
+    //One step of the side being optimised, the next side takes over when its phase is over
+    pub fn step(&mut self, game: &mut Game, fitness: &dyn Fitness, n_games: u32) -> Step {
+        let opponent_side = (self.side + 1) % self.climbers.len();
+        let champions = match &self.hall_of_fame {
+            Some(hall_of_fame) if self.champions > 0 => hall_of_fame.sample(
+                opponent_side,
+                (self.round, self.side),
+                self.champions,
+                self.seed,
+            ),
+            _ => Default::default(),
+        };
+        let fitness = WithChampions { fitness, champions };
+
+        let climber = &mut self.climbers[self.side];
+        if self.phase_iteration == 0 {
+            climber.rebase(game);
+        }
+        let step = climber.step(game, &fitness, n_games);
+        if let (Some(hall_of_fame), true) = (&mut self.hall_of_fame, step.accepted) {
+            hall_of_fame.push(
+                self.round,
+                self.side,
+                self.phase_iteration,
+                step.score,
+                &climber.best_deck,
+            );
+        }
+        self.phase_iteration += 1;
+        self.games += step.games as u64;
+
+        let phase = &climber.history[climber.history.len() - self.phase_iteration..];
+        self.stop = self.stopping.budget_stop(self.elapsed, self.games);
+        let phase_stop = match self.stop {
+            Some(reason) => Some(reason),
+            //A resumed run may be given fewer iterations than its phase has already run
+            None if self.phase_iteration >= self.iterations => Some(StopReason::Iterations),
+            None => self.stopping.phase_stop(phase),
+        };
+
+        if let Some(reason) = phase_stop {
+            let start_win_rate = phase[0].score;
+            let accepted = phase[1..].iter().filter(|step| step.accepted).count();
+            self.games += climber.finish(game, &fitness, n_games) as u64;
+            self.phases.push(PhaseResult {
+                round: self.round,
+                side: self.side,
+                start_win_rate,
+                end_win_rate: climber.best_score,
+                accepted,
+                iterations: self.phase_iteration,
+                stop: reason,
+            });
+
+            self.phase_iteration = 0;
+            if self.climbers.len() > 1 {
+                game.switch_player();
+            }
+            self.side += 1;
+            if self.side == self.climbers.len() {
+                self.side = 0;
+                self.round += 1;
+            }
+        }
+
+        step
+    }
+}
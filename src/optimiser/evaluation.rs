@@ -0,0 +1,77 @@
+use crate::game::{card_stats::CardStats, Game};
+
+//Result of p1's base deck playing n games as first player and n games as second player against
+//each of its opponents
+#[derive(Debug, Default, Clone)]
+pub struct Evaluation {
+    //Win rate, weighted by the share of each opponent
+    pub score: f64,
+    pub wins: u32,
+    pub games: u32,
+    pub avg_turn: f32,
+    //Empty unless the game tracks card stats
+    pub card_stats: CardStats,
+    pub matchups: Vec<Matchup>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Matchup {
+    pub name: String,
+    pub weight: f64,
+    pub wins: u32,
+    pub games: u32,
+    pub avg_turn: f32,
+}
+
+impl Matchup {
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games as f64
+    }
+}
+
+//Scores p1's base deck, any optimiser can use any fitness. Fitnesses are shared between the
+//threads evaluating a neighbourhood.
+pub trait Fitness: Sync {
+    fn evaluate(&self, game: &mut Game, n_games: u32) -> Evaluation;
+}
+
+//The original fitness: win rate against p2's base deck
+pub struct AgainstOpponent;
+
+impl Fitness for AgainstOpponent {
+    fn evaluate(&self, game: &mut Game, n_games: u32) -> Evaluation {
+        let evaluation = evaluate(game, n_games);
+        Evaluation {
+            matchups: vec![Matchup {
+                name: "opponent".to_owned(),
+                weight: 1.0,
+                wins: evaluation.wins,
+                games: evaluation.games,
+                avg_turn: evaluation.avg_turn,
+            }],
+            ..evaluation
+        }
+    }
+}
+
+pub fn evaluate(game: &mut Game, n_games: u32) -> Evaluation {
+    let (wins1, turns1, [mut card_stats, _]) = game.play_games(n_games as usize);
+    //switch player
+    std::mem::swap(&mut game.p1, &mut game.p2);
+
+    let (wins2, turns2, [_, second_player_stats]) = game.play_games(n_games as usize);
+
+    //switch player back
+    std::mem::swap(&mut game.p1, &mut game.p2);
+    card_stats.merge(&second_player_stats);
+
+    let wins = wins1 + n_games - wins2;
+    Evaluation {
+        score: wins as f64 / (n_games * 2) as f64,
+        wins,
+        games: n_games * 2,
+        avg_turn: (turns1 + turns2) as f32 / (n_games as f32 * 2.0),
+        card_stats,
+        matchups: vec![],
+    }
+}
@@ -0,0 +1,151 @@
+use std::{fs, fs::File, io::Write, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::evaluation::{evaluate, Evaluation, Fitness, Matchup};
+use crate::game::{
+    card::Card,
+    deck::{Deck, DeckTrait},
+    setlist::SetList,
+    Game,
+};
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+//An opponent of the gauntlet, the weight is its share of the meta
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct GauntletDeck {
+    pub name: String,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    //Deck file read when loading, relative to the gauntlet file. Saved gauntlets store the cards.
+    #[serde(default, skip_serializing)]
+    path: Option<String>,
+    #[serde(default)]
+    cards: Vec<Card>,
+}
+
+impl GauntletDeck {
+    pub fn new(name: &str, weight: f64, deck: &Deck) -> GauntletDeck {
+        GauntletDeck {
+            name: name.to_owned(),
+            weight,
+            path: None,
+            cards: deck.to_vec(),
+        }
+    }
+
+    pub fn deck(&self) -> Deck {
+        self.cards.clone().try_into().unwrap()
+    }
+}
+
+//{"Decks": [{"Name": "aggro", "Weight": 0.4, "Path": "aggro.data"}, {"Name": ..., "Cards": [...]}]}
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Gauntlet {
+    pub decks: Vec<GauntletDeck>,
+}
+
+impl Gauntlet {
+    //Comma separated gauntlet files or deck files (as written by the optimiser), the cards of deck
+    //files are looked up by id in the setlist
+    pub fn load(paths: &str, setlist: &SetList) -> Gauntlet {
+        let mut gauntlet = Gauntlet::default();
+        for path in paths.split(',').map(str::trim) {
+            let content = fs::read_to_string(path).unwrap();
+            let value: serde_json::Value = serde_json::from_str(&content).unwrap();
+            if value.get("Decks").is_none() {
+                let name = Path::new(path).file_stem().unwrap().to_string_lossy();
+                gauntlet
+                    .decks
+                    .push(GauntletDeck::new(&name, 1.0, &Deck::load(path, setlist)));
+                continue;
+            }
+
+            let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+            let loaded: Gauntlet = serde_json::from_value(value).unwrap();
+            for mut deck in loaded.decks {
+                if let Some(deck_path) = deck.path.take() {
+                    let deck_path = directory.join(deck_path);
+                    deck.cards = Deck::load(deck_path.to_str().unwrap(), setlist).to_vec();
+                }
+                if deck.cards.len() != 30 {
+                    panic!(
+                        "{path}: deck {} has {} cards instead of 30",
+                        deck.name,
+                        deck.cards.len()
+                    );
+                }
+                gauntlet.decks.push(deck);
+            }
+        }
+
+        if gauntlet.decks.iter().map(|deck| deck.weight).sum::<f64>() <= 0.0 {
+            panic!("The gauntlet {paths} has no deck with a positive weight");
+        }
+        gauntlet
+    }
+
+    pub fn export_matchups(evaluation: &Evaluation, file: &mut File) {
+        file.write_all(b"Opponent,Weight,Wins,Games,WinRate,AvgTurn\n")
+            .unwrap();
+        for matchup in &evaluation.matchups {
+            file.write_all(
+                format!(
+                    "{},{},{},{},{:.4},{}\n",
+                    matchup.name,
+                    matchup.weight,
+                    matchup.wins,
+                    matchup.games,
+                    matchup.win_rate(),
+                    matchup.avg_turn
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+        }
+        file.write_all(
+            format!(
+                "Total,,{},{},{:.4},{}\n",
+                evaluation.wins, evaluation.games, evaluation.score, evaluation.avg_turn
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    }
+}
+
+//Plays p1's base deck against every deck of the gauntlet, p2's base deck is left untouched
+impl Fitness for Gauntlet {
+    fn evaluate(&self, game: &mut Game, n_games: u32) -> Evaluation {
+        let opponent = game.p2.base_deck;
+        let total_weight: f64 = self.decks.iter().map(|deck| deck.weight).sum();
+
+        let mut evaluation = Evaluation::default();
+        for deck in &self.decks {
+            game.p2.base_deck = deck.deck();
+            let matchup = evaluate(game, n_games);
+            let share = deck.weight / total_weight;
+
+            evaluation.score += share * matchup.score;
+            evaluation.avg_turn += share as f32 * matchup.avg_turn;
+            evaluation.wins += matchup.wins;
+            evaluation.games += matchup.games;
+            evaluation.card_stats.merge(&matchup.card_stats);
+            evaluation.matchups.push(Matchup {
+                name: deck.name.clone(),
+                weight: deck.weight,
+                wins: matchup.wins,
+                games: matchup.games,
+                avg_turn: matchup.avg_turn,
+            });
+        }
+
+        game.p2.base_deck = opponent;
+        evaluation
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs;
+
+use rand::{seq::index, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    evaluation::{Evaluation, Fitness},
+    gauntlet::{Gauntlet, GauntletDeck},
+};
+use crate::game::{deck::Deck, Game};
+
+//An accepted deck, the file of the hall of fame can be loaded as a gauntlet
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct Champion {
+    #[serde(flatten)]
+    pub deck: GauntletDeck,
+    pub round: usize,
+    pub side: usize,
+    pub iteration: usize,
+    pub score: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct HallOfFame {
+    pub decks: Vec<Champion>,
+}
+
+impl HallOfFame {
+    pub fn save(&self, path: &str) {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap()).unwrap();
+    }
+
+    //The same deck accepted again by its side is only stored once in a row
+    pub fn push(&mut self, round: usize, side: usize, iteration: usize, score: f64, deck: &Deck) {
+        let last = self
+            .decks
+            .iter()
+            .rev()
+            .find(|champion| champion.side == side);
+        if last.is_some_and(|champion| champion.deck.deck() == *deck) {
+            return;
+        }
+
+        let name = format!("{}{round}-{iteration}", ["A", "B"][side]);
+        self.decks.push(Champion {
+            deck: GauntletDeck::new(&name, 1.0, deck),
+            round,
+            side,
+            iteration,
+            score,
+        });
+    }
+
+    //Up to n champions of a side accepted in the phases before (round, phase_side), always the same
+    //for the same seed so that every candidate of a phase faces the same sample
+    pub fn sample(
+        &self,
+        side: usize,
+        (round, phase_side): (usize, usize),
+        n: usize,
+        seed: u64,
+    ) -> Gauntlet {
+        let candidates: Vec<&Champion> = self
+            .decks
+            .iter()
+            .filter(|champion| {
+                champion.side == side && (champion.round, champion.side) < (round, phase_side)
+            })
+            .collect();
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed ^ (round * 2 + phase_side) as u64);
+        let mut gauntlet = Gauntlet::default();
+        for i in index::sample(&mut rng, candidates.len(), n.min(candidates.len())) {
+            gauntlet.decks.push(candidates[i].deck.clone());
+        }
+        gauntlet
+    }
+}
+
+//The usual fitness plus past champions, the sample counts as much as the usual opponents
+pub struct WithChampions<'a> {
+    pub fitness: &'a dyn Fitness,
+    pub champions: Gauntlet,
+}
+
+impl Fitness for WithChampions<'_> {
+    fn evaluate(&self, game: &mut Game, n_games: u32) -> Evaluation {
+        let mut evaluation = self.fitness.evaluate(game, n_games);
+        if self.champions.decks.is_empty() {
+            return evaluation;
+        }
+
+        let champions = self.champions.evaluate(game, n_games);
+        evaluation.score = (evaluation.score + champions.score) / 2.0;
+        evaluation.avg_turn = (evaluation.avg_turn + champions.avg_turn) / 2.0;
+        evaluation.wins += champions.wins;
+        evaluation.games += champions.games;
+        evaluation.card_stats.merge(&champions.card_stats);
+        evaluation.matchups.extend(champions.matchups);
+        evaluation
+    }
+}
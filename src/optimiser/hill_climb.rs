@@ -0,0 +1,336 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    evaluation::Fitness,
+    neighbourhood::{candidates, evaluate_upgrades, Upgrade},
+    pareto::ParetoFront,
+    proposal::{Proposal, Proposer, ProposerState},
+    search::{Restart, Search},
+    stopping::Stopping,
+};
+use crate::game::{
+    card::Card,
+    card_stats::CardStats,
+    deck::{Deck, DeckTrait},
+    setlist::SetList,
+    Game,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "PascalCase")]
+pub struct Step {
+    pub iteration: usize,
+    pub score: f64,
+    pub avg_turn: f32,
+    pub accepted: bool,
+    #[serde(default)]
+    pub games: u32,
+}
+
+//A swap evaluated by the climber, with the score of the deck it was applied to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "PascalCase")]
+pub struct Swap {
+    pub iteration: usize,
+    pub slot: usize,
+    pub outgoing: Card,
+    pub incoming: Card,
+    pub score_before: f64,
+    pub score_after: f64,
+    pub accepted: bool,
+}
+
+//Everything the hill climber needs to continue a run exactly
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct ClimberState {
+    pub iteration: usize,
+    pub best_deck: Deck,
+    pub best_score: f64,
+    pub best_card_stats: CardStats,
+    pub proposal: Option<Proposal>,
+    pub proposer: ProposerState,
+    pub rng: ChaCha8Rng,
+    pub history: Vec<Step>,
+    #[serde(default)]
+    pub pareto: Option<ParetoFront>,
+    #[serde(default)]
+    pub search: Search,
+    #[serde(default)]
+    pub steepest: Option<usize>,
+}
+
+//Optimises p1's base deck one card swap at a time, a swap is kept when it scores at least as well.
+//With a Pareto front the swap is judged on every objective of the front instead.
+//The search adds a tabu list of recent swaps and restarts when the climber gets stuck.
+//In steepest ascent mode each step evaluates a neighbourhood of swaps (every distinct card of the
+//deck against a sample of that many setlist cards) and keeps the best one when its gain is above
+//the noise of the games, Pareto fronts and the tabu list do not apply.
+pub struct HillClimber {
+    pub iteration: usize,
+    pub best_deck: Deck,
+    pub best_score: f64,
+    best_card_stats: CardStats,
+    proposal: Option<Proposal>,
+    pub proposer: Proposer,
+    rng: ChaCha8Rng,
+    pub history: Vec<Step>,
+    pub pareto: Option<ParetoFront>,
+    pub search: Search,
+    pub steepest: Option<usize>,
+    //Swaps evaluated by the last steepest ascent step, best first
+    pub upgrades: Vec<Upgrade>,
+    //Swaps evaluated by the last step, every one of the neighbourhood best first in steepest ascent
+    //mode, none when the step evaluated a new deck
+    pub last_swaps: Vec<Swap>,
+}
+
+impl HillClimber {
+    //The proposals use their own stream of the seed, independent from the games (stream 0)
+    pub fn new(game: &mut Game, proposer: Proposer, seed: u64, stream: u64) -> HillClimber {
+        game.track_card_stats = proposer.needs_card_stats();
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(stream);
+
+        HillClimber {
+            iteration: 0,
+            best_deck: game.p1.base_deck,
+            best_score: 0.0,
+            best_card_stats: CardStats::default(),
+            proposal: None,
+            proposer,
+            rng,
+            history: vec![],
+            pareto: None,
+            search: Search::default(),
+            steepest: None,
+            upgrades: vec![],
+            last_swaps: vec![],
+        }
+    }
+
+    pub fn restore(setlist: SetList, state: ClimberState) -> HillClimber {
+        HillClimber {
+            iteration: state.iteration,
+            best_deck: state.best_deck,
+            best_score: state.best_score,
+            best_card_stats: state.best_card_stats,
+            proposal: state.proposal,
+            proposer: Proposer::restore(setlist, state.proposer),
+            rng: state.rng,
+            history: state.history,
+            pareto: state.pareto,
+            search: state.search,
+            steepest: state.steepest,
+            upgrades: vec![],
+            last_swaps: vec![],
+        }
+    }
+
+    pub fn state(&self) -> ClimberState {
+        ClimberState {
+            iteration: self.iteration,
+            best_deck: self.best_deck,
+            best_score: self.best_score,
+            best_card_stats: self.best_card_stats.clone(),
+            proposal: self.proposal,
+            proposer: self.proposer.state(),
+            rng: self.rng.clone(),
+            history: self.history.clone(),
+            pareto: self.pareto.clone(),
+            search: self.search.clone(),
+            steepest: self.steepest,
+        }
+    }
+
+    //Starts again from p1's base deck, whose score is measured again by the next step since the
+    //opponent may have changed
+    pub fn rebase(&mut self, game: &Game) {
+        self.best_deck = game.p1.base_deck;
+        self.best_score = 0.0;
+        self.proposal = None;
+        if let Some(pareto) = &mut self.pareto {
+            pareto.rebase();
+        }
+        self.search.rebase();
+    }
+
+    //Leaves the best deck in place of the last proposal, which has not been evaluated. After
+    //restarts the best deck may be one the climber has left: its score is the highest of many noisy
+    //evaluations, so both decks are evaluated again on the same games and the better one is kept.
+    //Returns the games played.
+    pub fn finish(&mut self, game: &mut Game, fitness: &dyn Fitness, n_games: u32) -> u32 {
+        let mut games = 0;
+        if let (Restart::Random | Restart::Iterated, Some((deck, score))) =
+            (self.search.config.restart, self.search.best)
+        {
+            if self.best_score < score && self.best_deck != deck {
+                let rng = game.rng.clone();
+                game.p1.base_deck = self.best_deck;
+                let current = fitness.evaluate(game, n_games);
+                game.rng = rng;
+                game.p1.base_deck = deck;
+                let best = fitness.evaluate(game, n_games);
+                games = current.games + best.games;
+                if current.score < best.score {
+                    self.best_deck = deck;
+                    self.best_score = best.score;
+                } else {
+                    self.best_score = current.score;
+                }
+            }
+        }
+        game.p1.base_deck = self.best_deck;
+        games
+    }
+
+    //Evaluates p1's base deck, keeps or reverts the last swap then applies the next one
+    pub fn step(&mut self, game: &mut Game, fitness: &dyn Fitness, n_games: u32) -> Step {
+        if let Some(sample) = self.steepest {
+            return self.steepest_step(game, fitness, n_games, sample);
+        }
+
+        let evaluation = fitness.evaluate(game, n_games);
+        let restart = self.search.observe(&game.p1.base_deck, evaluation.score);
+
+        let accepted = match &mut self.pareto {
+            Some(pareto) => pareto.accept(self.iteration, &game.p1.base_deck, &evaluation),
+            None => self.best_score <= evaluation.score,
+        };
+        if let Some(proposal) = &self.proposal {
+            self.proposer.feedback(proposal, accepted);
+        }
+        self.last_swaps = Vec::from_iter(self.proposal.map(|proposal| Swap {
+            iteration: self.iteration,
+            slot: proposal.slot,
+            outgoing: proposal.outgoing,
+            incoming: proposal.incoming,
+            score_before: self.best_score,
+            score_after: evaluation.score,
+            accepted,
+        }));
+
+        //Update deck
+        if accepted {
+            self.best_score = evaluation.score;
+            self.best_deck = game.p1.base_deck;
+            self.best_card_stats = evaluation.card_stats;
+        } else {
+            game.p1.base_deck = self.best_deck;
+        }
+
+        if restart {
+            self.restart(game);
+        } else {
+            //Without a legal swap the next step evaluates the best deck again
+            self.proposal = self.search.choose(self.iteration, || {
+                self.proposer.propose(
+                    &game.p1.base_deck,
+                    Some(&self.best_card_stats),
+                    &mut self.rng,
+                )
+            });
+            if let Some(proposal) = &self.proposal {
+                game.p1.base_deck[proposal.slot] = proposal.incoming;
+            }
+        }
+
+        let step = Step {
+            iteration: self.iteration,
+            score: evaluation.score,
+            avg_turn: evaluation.avg_turn,
+            accepted,
+            games: evaluation.games,
+        };
+        self.history.push(step);
+        self.iteration += 1;
+        step
+    }
+
+    //p1's base deck always is the best deck, the neighbourhood games have their own seed
+    fn steepest_step(
+        &mut self,
+        game: &mut Game,
+        fitness: &dyn Fitness,
+        n_games: u32,
+        sample: usize,
+    ) -> Step {
+        let swaps = candidates(&game.p1.base_deck, &game.setlist, sample, &mut self.rng);
+        let seed = self.rng.gen();
+        let (evaluation, upgrades) = evaluate_upgrades(game, fitness, n_games, &swaps, seed);
+
+        let best = upgrades.first().copied();
+        //The best of many swaps is lucky, a gain within the noise of the games is not kept
+        let accepted = best.is_some_and(|upgrade| upgrade.gain > Stopping::noise(n_games));
+        let (score, avg_turn) = match best {
+            Some(upgrade) if accepted => {
+                game.p1.base_deck[upgrade.slot] = upgrade.incoming;
+                (upgrade.score, upgrade.avg_turn)
+            }
+            _ => (evaluation.score, evaluation.avg_turn),
+        };
+        if let Some(upgrade) = best {
+            let proposal = Proposal {
+                slot: upgrade.slot,
+                card_index: upgrade.card_index,
+                outgoing: upgrade.outgoing,
+                incoming: upgrade.incoming,
+            };
+            self.proposer.feedback(&proposal, accepted);
+        }
+        //Only the best swap can be the one kept
+        self.last_swaps = upgrades
+            .iter()
+            .enumerate()
+            .map(|(rank, upgrade)| Swap {
+                iteration: self.iteration,
+                slot: upgrade.slot,
+                outgoing: upgrade.outgoing,
+                incoming: upgrade.incoming,
+                score_before: evaluation.score,
+                score_after: upgrade.score,
+                accepted: accepted && rank == 0,
+            })
+            .collect();
+        self.best_deck = game.p1.base_deck;
+        self.best_score = score;
+        self.upgrades = upgrades;
+
+        if self.search.observe(&self.best_deck, score) {
+            self.restart(game);
+        }
+
+        let step = Step {
+            iteration: self.iteration,
+            score,
+            avg_turn,
+            accepted,
+            games: evaluation.games * (swaps.len() as u32 + 1),
+        };
+        self.history.push(step);
+        self.iteration += 1;
+        step
+    }
+
+    //The next step evaluates the new deck and always keeps it
+    fn restart(&mut self, game: &mut Game) {
+        game.p1.base_deck = match (self.search.config.restart, self.search.best) {
+            (Restart::Iterated, Some((mut deck, _))) => {
+                for _ in 0..self.search.config.kick {
+                    if let Some(kick) = self.proposer.propose(&deck, None, &mut self.rng) {
+                        deck[kick.slot] = kick.incoming;
+                    }
+                }
+                deck
+            }
+            _ => Deck::new(&game.setlist, &mut self.rng),
+        };
+        self.best_score = 0.0;
+        if let Some(pareto) = &mut self.pareto {
+            pareto.restart();
+        }
+        self.proposal = None;
+    }
+}
@@ -0,0 +1,242 @@
+use std::{fs, fs::File, io::Write};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    evaluation::{Evaluation, Fitness},
+    gauntlet::GauntletDeck,
+    proposal::Proposer,
+};
+use crate::game::{
+    card::{Card, Keyword},
+    deck::{Deck, DeckTrait},
+    Game,
+};
+
+//Measure of a deck placing it in the behaviour space
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Descriptor {
+    AvgCost,
+    AvgAttack,
+    AvgDefense,
+    AvgTurn,
+    //Share of the deck with the keyword
+    Keyword(Keyword),
+}
+
+impl Descriptor {
+    pub fn from_name(name: &str) -> Option<Descriptor> {
+        match name.to_lowercase().as_str() {
+            "avg-cost" | "cost" => Some(Descriptor::AvgCost),
+            "avg-attack" | "attack" => Some(Descriptor::AvgAttack),
+            "avg-defense" | "defense" => Some(Descriptor::AvgDefense),
+            "avg-turn" | "turn" => Some(Descriptor::AvgTurn),
+            _ => Keyword::from_name(name).map(Descriptor::Keyword),
+        }
+    }
+
+    fn value(&self, deck: &Deck, evaluation: &Evaluation) -> f64 {
+        let average = |value: fn(&Card) -> u32| {
+            deck.iter().map(|card| value(card) as f64).sum::<f64>() / deck.len() as f64
+        };
+        match self {
+            Descriptor::AvgCost => average(|card| card.get_cost()),
+            Descriptor::AvgAttack => average(|card| card.get_attack()),
+            Descriptor::AvgDefense => average(|card| card.get_defense()),
+            Descriptor::AvgTurn => evaluation.avg_turn as f64,
+            Descriptor::Keyword(keyword) => {
+                deck.iter()
+                    .filter(|card| card.has_keyword(*keyword))
+                    .count() as f64
+                    / deck.len() as f64
+            }
+        }
+    }
+}
+
+//Bins of equal width between min and max, values outside fall in the first or last bin
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct Axis {
+    pub name: String,
+    pub descriptor: Descriptor,
+    pub min: f64,
+    pub max: f64,
+    pub bins: usize,
+}
+
+impl Axis {
+    fn bin(&self, value: f64) -> usize {
+        let position = (value - self.min) / (self.max - self.min) * self.bins as f64;
+        (position.max(0.0) as usize).min(self.bins - 1)
+    }
+
+    pub fn bin_start(&self, bin: usize) -> f64 {
+        self.min + (self.max - self.min) * bin as f64 / self.bins as f64
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct Elite {
+    #[serde(flatten)]
+    pub deck: GauntletDeck,
+    pub iteration: usize,
+    pub score: f64,
+    pub avg_turn: f32,
+    pub behaviour: Vec<f64>,
+    pub cell: Vec<usize>,
+}
+
+//Best deck found in each cell of the grid. Its file can be loaded as a gauntlet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct Archive {
+    pub axes: Vec<Axis>,
+    pub decks: Vec<Elite>,
+}
+
+impl Archive {
+    pub fn new(axes: Vec<Axis>) -> Archive {
+        Archive {
+            axes,
+            decks: vec![],
+        }
+    }
+
+    pub fn n_cells(&self) -> usize {
+        self.axes.iter().map(|axis| axis.bins).product()
+    }
+
+    pub fn get(&self, cell: &[usize]) -> Option<&Elite> {
+        self.decks.iter().find(|elite| elite.cell == cell)
+    }
+
+    //The deck takes its cell when the cell is empty or holds a lower score
+    pub fn insert(&mut self, iteration: usize, deck: &Deck, evaluation: &Evaluation) -> bool {
+        let behaviour: Vec<f64> = self
+            .axes
+            .iter()
+            .map(|axis| axis.descriptor.value(deck, evaluation))
+            .collect();
+        let cell: Vec<usize> = self
+            .axes
+            .iter()
+            .zip(&behaviour)
+            .map(|(axis, value)| axis.bin(*value))
+            .collect();
+
+        let position = self.decks.iter().position(|elite| elite.cell == cell);
+        if position.is_some_and(|i| self.decks[i].score >= evaluation.score) {
+            return false;
+        }
+
+        let name = cell
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>()
+            .join("-");
+        let elite = Elite {
+            deck: GauntletDeck::new(&format!("Elite{name}"), 1.0, deck),
+            iteration,
+            score: evaluation.score,
+            avg_turn: evaluation.avg_turn,
+            behaviour,
+            cell,
+        };
+        match position {
+            Some(i) => self.decks[i] = elite,
+            None => self.decks.push(elite),
+        }
+        true
+    }
+
+    pub fn save(&self, path: &str) {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap()).unwrap();
+    }
+
+    //One row per elite with its bins and behaviour values, in cell order
+    pub fn export_csv(&self, file: &mut File) {
+        let mut header = vec!["Name".to_owned()];
+        for axis in &self.axes {
+            header.push(format!("{}Bin", axis.name));
+            header.push(axis.name.clone());
+        }
+        header.extend(["Score", "AvgTurn", "Iteration"].map(str::to_owned));
+        file.write_all(format!("{}\n", header.join(",")).as_bytes())
+            .unwrap();
+
+        let mut elites: Vec<&Elite> = self.decks.iter().collect();
+        elites.sort_by(|a, b| a.cell.cmp(&b.cell));
+        for elite in elites {
+            let mut row = vec![elite.deck.name.clone()];
+            for (bin, value) in elite.cell.iter().zip(&elite.behaviour) {
+                row.push(bin.to_string());
+                row.push(format!("{value:.3}"));
+            }
+            row.push(format!("{:.4}", elite.score));
+            row.push(elite.avg_turn.to_string());
+            row.push(elite.iteration.to_string());
+            file.write_all(format!("{}\n", row.join(",")).as_bytes())
+                .unwrap();
+        }
+    }
+}
+
+//MAP-Elites: random decks fill the archive first, then each iteration mutates a random elite with
+//the single card swap of the hill climber and offers the result to the archive
+pub struct MapElites {
+    pub archive: Archive,
+    pub proposer: Proposer,
+    pub iteration: usize,
+    random_decks: usize,
+    rng: ChaCha8Rng,
+}
+
+impl MapElites {
+    //The mutations use their own stream of the seed, independent from the games (stream 0)
+    pub fn new(archive: Archive, proposer: Proposer, random_decks: usize, seed: u64) -> MapElites {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(1);
+
+        MapElites {
+            archive,
+            proposer,
+            iteration: 0,
+            random_decks,
+            rng,
+        }
+    }
+
+    //Plays the new deck as p1, p1's base deck is left to the last deck evaluated
+    pub fn step(&mut self, game: &mut Game, fitness: &dyn Fitness, n_games: u32) -> bool {
+        let proposal = if self.iteration < self.random_decks || self.archive.decks.is_empty() {
+            game.p1.base_deck = Deck::new(&game.setlist, &mut self.rng);
+            None
+        } else {
+            let parent = self.rng.gen_range(0..self.archive.decks.len());
+            game.p1.base_deck = self.archive.decks[parent].deck.deck();
+            //The parent is evaluated again when no card can enter it
+            let proposal = self
+                .proposer
+                .propose(&game.p1.base_deck, None, &mut self.rng);
+            if let Some(proposal) = &proposal {
+                game.p1.base_deck[proposal.slot] = proposal.incoming;
+            }
+            proposal
+        };
+
+        let evaluation = fitness.evaluate(game, n_games);
+        let inserted = self
+            .archive
+            .insert(self.iteration, &game.p1.base_deck, &evaluation);
+        if let Some(proposal) = &proposal {
+            self.proposer.feedback(proposal, inserted);
+        }
+
+        self.iteration += 1;
+        inserted
+    }
+}
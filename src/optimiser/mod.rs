@@ -0,0 +1,13 @@
+pub mod checkpoint;
+pub mod coevolution;
+pub mod evaluation;
+pub mod gauntlet;
+pub mod hall_of_fame;
+pub mod hill_climb;
+pub mod map_elites;
+pub mod neighbourhood;
+pub mod pareto;
+pub mod proposal;
+pub mod run_log;
+pub mod search;
+pub mod stopping;
@@ -0,0 +1,126 @@
+use std::{fs::File, io::Write, iter};
+
+use rand::{seq::index, Rng};
+
+use super::evaluation::{Evaluation, Fitness};
+use crate::{
+    game::{
+        card::Card,
+        deck::{Deck, MAX_COPIES},
+        naming::CardNames,
+        Game,
+    },
+    parallel::parallel_map,
+};
+
+//A single card swap with its score measured against the same games as the unchanged deck
+#[derive(Debug, Clone, Copy)]
+pub struct Upgrade {
+    pub slot: usize,
+    pub outgoing: Card,
+    pub card_index: usize,
+    pub incoming: Card,
+    pub score: f64,
+    pub avg_turn: f32,
+    pub gain: f64,
+}
+
+//Every distinct card of the deck against the same sample of setlist cards, or the whole setlist
+//when the sample is larger. Cards the deck already holds MAX_COPIES times are left out.
+pub fn candidates<R: Rng + ?Sized>(
+    deck: &Deck,
+    setlist: &[Card],
+    sample: usize,
+    rng: &mut R,
+) -> Vec<(usize, usize)> {
+    let mut incoming: Vec<usize> = index::sample(rng, setlist.len(), sample.min(setlist.len()))
+        .into_vec()
+        .into_iter()
+        .filter(|&index| deck.iter().filter(|card| **card == setlist[index]).count() < MAX_COPIES)
+        .collect();
+    incoming.sort();
+
+    let mut outgoing: Vec<usize> = vec![];
+    for (slot, card) in deck.iter().enumerate() {
+        if !outgoing.iter().any(|&other| deck[other] == *card) {
+            outgoing.push(slot);
+        }
+    }
+
+    outgoing
+        .iter()
+        .flat_map(|&slot| {
+            incoming
+                .iter()
+                .filter(move |&&index| setlist[index] != deck[slot])
+                .map(move |&index| (slot, index))
+        })
+        .collect()
+}
+
+//Evaluates p1's base deck and every swap in parallel. All of them play games seeded with the same
+//seed so that the gains compare decks on the same draws. Returns the unchanged deck's
+//evaluation and the upgrades, best first.
+pub fn evaluate_upgrades(
+    game: &Game,
+    fitness: &dyn Fitness,
+    n_games: u32,
+    swaps: &[(usize, usize)],
+    seed: u64,
+) -> (Evaluation, Vec<Upgrade>) {
+    let (deck, opponent, setlist) = (game.p1.base_deck, game.p2.base_deck, &game.setlist);
+    let play = |swap: Option<(usize, usize)>| {
+        let mut game = Game::seeded(setlist.clone(), seed);
+        game.p1.base_deck = deck;
+        game.p2.base_deck = opponent;
+        if let Some((slot, card_index)) = swap {
+            game.p1.base_deck[slot] = setlist[card_index];
+        }
+        fitness.evaluate(&mut game, n_games)
+    };
+
+    //The unchanged deck first
+    let jobs: Vec<Option<(usize, usize)>> = iter::once(None)
+        .chain(swaps.iter().copied().map(Some))
+        .collect();
+    let mut evaluations = parallel_map(&jobs, |_, job| play(*job));
+
+    let base = evaluations.remove(0);
+    let mut upgrades: Vec<Upgrade> = swaps
+        .iter()
+        .zip(&evaluations)
+        .map(|(&(slot, card_index), evaluation)| Upgrade {
+            slot,
+            outgoing: deck[slot],
+            card_index,
+            incoming: setlist[card_index],
+            score: evaluation.score,
+            avg_turn: evaluation.avg_turn,
+            gain: evaluation.score - base.score,
+        })
+        .collect();
+    upgrades.sort_by(|a, b| b.gain.total_cmp(&a.gain));
+    (base, upgrades)
+}
+
+pub fn export_upgrades(upgrades: &[Upgrade], names: &CardNames, file: &mut File) {
+    file.write_all(b"Rank,Slot,OutgoingId,Outgoing,IncomingId,Incoming,Score,Gain\n")
+        .unwrap();
+    for (rank, upgrade) in upgrades.iter().enumerate() {
+        file.write_all(
+            format!(
+                "{},{},{},{},{},{},{:.4},{:.4}\n",
+                rank + 1,
+                upgrade.slot,
+                upgrade.outgoing.get_id(),
+                names.label(&upgrade.outgoing),
+                upgrade.incoming.get_id(),
+                names.label(&upgrade.incoming),
+                upgrade.score,
+                upgrade.gain
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+use std::{fs, fs::File, io::Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::{evaluation::Evaluation, gauntlet::GauntletDeck};
+use crate::game::deck::Deck;
+
+//Targets of the search besides the win rate, the search is single objective without any
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Objectives {
+    pub target_turn: Option<f32>,
+    //Share of the deck at each cost starting at 1, the first entry also counts free cards and the
+    //last one every card costing at least as much
+    pub target_curve: Option<Vec<f64>>,
+}
+
+impl Objectives {
+    pub fn is_empty(&self) -> bool {
+        self.target_turn.is_none() && self.target_curve.is_none()
+    }
+
+    //Every value is maximised: the win rate, then minus the distance to each target
+    fn values(&self, deck: &Deck, evaluation: &Evaluation) -> Vec<f64> {
+        let mut values = vec![evaluation.score];
+        if let Some(target) = self.target_turn {
+            values.push(-(evaluation.avg_turn - target).abs() as f64);
+        }
+        if let Some(target) = &self.target_curve {
+            values.push(-curve_error(deck, target));
+        }
+        values
+    }
+}
+
+//Number of cards to move to another cost to follow the target curve, scaled to 30 cards
+pub fn curve_error(deck: &Deck, target: &[f64]) -> f64 {
+    let total: f64 = target.iter().sum();
+    let mut counts = vec![0.0; target.len()];
+    for card in deck {
+        counts[(card.get_cost().max(1) as usize - 1).min(target.len() - 1)] += 1.0;
+    }
+    counts
+        .iter()
+        .zip(target)
+        .map(|(count, share)| (count - share * 30.0 / total).abs())
+        .sum::<f64>()
+        / 2.0
+}
+
+fn dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b).all(|(a, b)| a >= b) && a.iter().zip(b).any(|(a, b)| a > b)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct ParetoDeck {
+    #[serde(flatten)]
+    pub deck: GauntletDeck,
+    pub iteration: usize,
+    pub win_rate: f64,
+    pub avg_turn: f32,
+    pub avg_cost: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curve_error: Option<f64>,
+    values: Vec<f64>,
+}
+
+//Decks that no other evaluated deck beats on every objective. Its file can be loaded as a gauntlet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct ParetoFront {
+    pub objectives: Objectives,
+    pub decks: Vec<ParetoDeck>,
+    //Objective values of the deck the search currently stands on
+    current: Vec<f64>,
+}
+
+impl ParetoFront {
+    pub fn new(objectives: Objectives) -> ParetoFront {
+        ParetoFront {
+            objectives,
+            decks: vec![],
+            current: vec![],
+        }
+    }
+
+    //The values of the front are only comparable against the same opponents, co-evolution runs
+    //save the front of each phase before it is cleared
+    pub fn rebase(&mut self) {
+        self.decks.clear();
+        self.current.clear();
+    }
+
+    //The next deck is kept whatever its values, the front is kept
+    pub fn restart(&mut self) {
+        self.current.clear();
+    }
+
+    //A swap is kept when the deck it left does not dominate the candidate and no deck of the front
+    //does either, trade-offs between objectives are kept as long as they extend the front
+    pub fn accept(&mut self, iteration: usize, deck: &Deck, evaluation: &Evaluation) -> bool {
+        let values = self.objectives.values(deck, evaluation);
+        let dominated = self
+            .decks
+            .iter()
+            .any(|member| dominates(&member.values, &values));
+        let accepted = self.current.is_empty() || !(dominated || dominates(&self.current, &values));
+
+        if !dominated && !self.decks.iter().any(|member| member.values == values) {
+            self.decks
+                .retain(|member| !dominates(&values, &member.values));
+            self.decks.push(ParetoDeck {
+                deck: GauntletDeck::new(&format!("Pareto{iteration}"), 1.0, deck),
+                iteration,
+                win_rate: evaluation.score,
+                avg_turn: evaluation.avg_turn,
+                avg_cost: deck.iter().map(|card| card.get_cost() as f64).sum::<f64>() / 30.0,
+                curve_error: self
+                    .objectives
+                    .target_curve
+                    .as_ref()
+                    .map(|target| curve_error(deck, target)),
+                values: values.clone(),
+            });
+        }
+        if accepted {
+            self.current = values;
+        }
+        accepted
+    }
+
+    pub fn save(&self, path: &str) {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap()).unwrap();
+    }
+
+    //Best win rate first
+    pub fn export_csv(&self, file: &mut File) {
+        file.write_all(b"Name,Iteration,WinRate,AvgTurn,AvgCost,CurveError\n")
+            .unwrap();
+        let mut decks: Vec<&ParetoDeck> = self.decks.iter().collect();
+        decks.sort_by(|a, b| b.win_rate.total_cmp(&a.win_rate));
+        for deck in decks {
+            file.write_all(
+                format!(
+                    "{},{},{:.4},{},{:.2},{}\n",
+                    deck.deck.name,
+                    deck.iteration,
+                    deck.win_rate,
+                    deck.avg_turn,
+                    deck.avg_cost,
+                    deck.curve_error
+                        .map_or(String::new(), |error| format!("{error:.2}"))
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,622 @@
+use std::{fs::File, io::Write};
+
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    card::Card,
+    card_stats::CardStats,
+    deck::{Deck, MAX_COPIES},
+    naming::CardNames,
+    setlist::SetList,
+    weighted_setlist::WeightedSetlist,
+};
+
+//How the incoming card is chosen and how its weight learns from the outcome
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CardProposal {
+    #[default]
+    Additive,
+    Multiplicative,
+    Exp3,
+    Thompson,
+}
+
+impl CardProposal {
+    pub fn from_name(name: &str) -> Option<CardProposal> {
+        match name.to_lowercase().as_str() {
+            "additive" => Some(CardProposal::Additive),
+            "multiplicative" => Some(CardProposal::Multiplicative),
+            "exp3" => Some(CardProposal::Exp3),
+            "thompson" => Some(CardProposal::Thompson),
+            _ => None,
+        }
+    }
+}
+
+//How the deck slot to replace is chosen
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SlotProposal {
+    #[default]
+    Uniform,
+    Contribution,
+}
+
+impl SlotProposal {
+    pub fn from_name(name: &str) -> Option<SlotProposal> {
+        match name.to_lowercase().as_str() {
+            "uniform" => Some(SlotProposal::Uniform),
+            "contribution" => Some(SlotProposal::Contribution),
+            _ => None,
+        }
+    }
+}
+
+//Learns which setlist cards are worth inserting, indexes are setlist indexes
+pub trait CardSelector {
+    //None when no card can be drawn
+    fn select(&mut self, rng: &mut dyn RngCore) -> Option<usize>;
+    fn update(&mut self, card_index: usize, accepted: bool);
+    //Learned value of each card, what it means depends on the strategy
+    fn weights(&self) -> Vec<f64>;
+    fn state(&self) -> SelectorState;
+}
+
+//Everything a selector learned, enough to rebuild it exactly
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "Strategy", rename_all_fields = "PascalCase")]
+pub enum SelectorState {
+    Additive { weights: Vec<u32>, step: i32 },
+    Multiplicative { weights: Vec<u32>, rate: f64 },
+    Exp3 { log_weights: Vec<f64>, gamma: f64 },
+    Thompson { alpha: Vec<f64>, beta: Vec<f64> },
+}
+
+impl SelectorState {
+    pub fn initial(cards: CardProposal, size: usize) -> SelectorState {
+        match cards {
+            CardProposal::Additive => SelectorState::Additive {
+                weights: vec![100; size],
+                step: 1,
+            },
+            CardProposal::Multiplicative => SelectorState::Multiplicative {
+                weights: vec![1000; size],
+                rate: 0.1,
+            },
+            CardProposal::Exp3 => SelectorState::Exp3 {
+                log_weights: vec![0.0; size],
+                gamma: 0.1,
+            },
+            CardProposal::Thompson => SelectorState::Thompson {
+                alpha: vec![1.0; size],
+                beta: vec![1.0; size],
+            },
+        }
+    }
+
+    fn build(self, setlist: &SetList) -> Box<dyn CardSelector> {
+        match self {
+            SelectorState::Additive { weights, step } => Box::new(AdditiveSelector {
+                weighted_setlist: WeightedSetlist::with_weights(setlist.clone(), weights),
+                step,
+            }),
+            SelectorState::Multiplicative { weights, rate } => Box::new(MultiplicativeSelector {
+                weighted_setlist: WeightedSetlist::with_weights(setlist.clone(), weights),
+                rate,
+            }),
+            SelectorState::Exp3 { log_weights, gamma } => {
+                Box::new(Exp3Selector { log_weights, gamma })
+            }
+            SelectorState::Thompson { alpha, beta } => Box::new(ThompsonSelector { alpha, beta }),
+        }
+    }
+}
+
+//Weight ±step, the original update of the optimiser
+struct AdditiveSelector {
+    weighted_setlist: WeightedSetlist,
+    step: i32,
+}
+
+impl CardSelector for AdditiveSelector {
+    //Every weight may have gone down to 0
+    fn select(&mut self, rng: &mut dyn RngCore) -> Option<usize> {
+        if self.weighted_setlist.get_sum_weights() == 0 {
+            return None;
+        }
+        Some(self.weighted_setlist.get_rand(rng).1)
+    }
+
+    fn update(&mut self, card_index: usize, accepted: bool) {
+        let change = if accepted { self.step } else { -self.step };
+        self.weighted_setlist.change_weight(card_index, change);
+    }
+
+    fn weights(&self) -> Vec<f64> {
+        (0..self.weighted_setlist.len())
+            .map(|index| self.weighted_setlist.get_weight(index) as f64)
+            .collect()
+    }
+
+    fn state(&self) -> SelectorState {
+        SelectorState::Additive {
+            weights: self.weighted_setlist.get_weights().to_vec(),
+            step: self.step,
+        }
+    }
+}
+
+//Weight multiplied by 1 ± rate, never below 1 so that no card becomes unreachable
+struct MultiplicativeSelector {
+    weighted_setlist: WeightedSetlist,
+    rate: f64,
+}
+
+impl CardSelector for MultiplicativeSelector {
+    fn select(&mut self, rng: &mut dyn RngCore) -> Option<usize> {
+        Some(self.weighted_setlist.get_rand(rng).1)
+    }
+
+    fn update(&mut self, card_index: usize, accepted: bool) {
+        let weight = self.weighted_setlist.get_weight(card_index) as f64;
+        let factor = if accepted {
+            1.0 + self.rate
+        } else {
+            1.0 - self.rate
+        };
+        let new_weight = (weight * factor).round().clamp(1.0, u32::MAX as f64);
+        self.weighted_setlist
+            .change_weight(card_index, (new_weight - weight) as i32);
+    }
+
+    fn weights(&self) -> Vec<f64> {
+        (0..self.weighted_setlist.len())
+            .map(|index| self.weighted_setlist.get_weight(index) as f64)
+            .collect()
+    }
+
+    fn state(&self) -> SelectorState {
+        SelectorState::Multiplicative {
+            weights: self.weighted_setlist.get_weights().to_vec(),
+            rate: self.rate,
+        }
+    }
+}
+
+//Adversarial bandit: the reward (1 when accepted) is importance weighted by the probability of
+//the card, weights are kept as logarithms
+struct Exp3Selector {
+    log_weights: Vec<f64>,
+    gamma: f64,
+}
+
+impl Exp3Selector {
+    fn probabilities(&self) -> Vec<f64> {
+        let max = self
+            .log_weights
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = self.log_weights.iter().map(|w| (w - max).exp()).collect();
+        let sum: f64 = weights.iter().sum();
+        let k = weights.len() as f64;
+        weights
+            .iter()
+            .map(|w| (1.0 - self.gamma) * w / sum + self.gamma / k)
+            .collect()
+    }
+}
+
+impl CardSelector for Exp3Selector {
+    fn select(&mut self, rng: &mut dyn RngCore) -> Option<usize> {
+        let probabilities = self.probabilities();
+        let mut target = rng.gen::<f64>();
+        for (index, probability) in probabilities.iter().enumerate() {
+            if target < *probability {
+                return Some(index);
+            }
+            target -= probability;
+        }
+        Some(probabilities.len() - 1)
+    }
+
+    fn update(&mut self, card_index: usize, accepted: bool) {
+        if !accepted {
+            return;
+        }
+        let probability = self.probabilities()[card_index];
+        let k = self.log_weights.len() as f64;
+        self.log_weights[card_index] += self.gamma / (probability * k);
+    }
+
+    fn weights(&self) -> Vec<f64> {
+        self.probabilities()
+    }
+
+    fn state(&self) -> SelectorState {
+        SelectorState::Exp3 {
+            log_weights: self.log_weights.clone(),
+            gamma: self.gamma,
+        }
+    }
+}
+
+//Bayesian bandit: each card has a Beta(accepted + 1, rejected + 1) posterior on its acceptance
+//rate, the card with the highest sampled rate is proposed
+struct ThompsonSelector {
+    alpha: Vec<f64>,
+    beta: Vec<f64>,
+}
+
+impl CardSelector for ThompsonSelector {
+    fn select(&mut self, rng: &mut dyn RngCore) -> Option<usize> {
+        let mut best = (0, f64::NEG_INFINITY);
+        for (index, (alpha, beta)) in self.alpha.iter().zip(&self.beta).enumerate() {
+            let x = sample_gamma(*alpha, rng);
+            let y = sample_gamma(*beta, rng);
+            let rate = x / (x + y);
+            if rate > best.1 {
+                best = (index, rate);
+            }
+        }
+        Some(best.0)
+    }
+
+    fn update(&mut self, card_index: usize, accepted: bool) {
+        if accepted {
+            self.alpha[card_index] += 1.0;
+        } else {
+            self.beta[card_index] += 1.0;
+        }
+    }
+
+    fn weights(&self) -> Vec<f64> {
+        self.alpha
+            .iter()
+            .zip(&self.beta)
+            .map(|(alpha, beta)| alpha / (alpha + beta))
+            .collect()
+    }
+
+    fn state(&self) -> SelectorState {
+        SelectorState::Thompson {
+            alpha: self.alpha.clone(),
+            beta: self.beta.clone(),
+        }
+    }
+}
+
+//Marsaglia and Tsang, shape >= 1
+fn sample_gamma(shape: f64, rng: &mut dyn RngCore) -> f64 {
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = sample_normal(rng);
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u = rng.gen::<f64>();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+//Box-Muller
+fn sample_normal(rng: &mut dyn RngCore) -> f64 {
+    let u1 = 1.0 - rng.gen::<f64>();
+    let u2 = rng.gen::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+//A single card swap, card_index is the index of the incoming card in the setlist
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "PascalCase")]
+pub struct Proposal {
+    pub slot: usize,
+    pub card_index: usize,
+    #[serde(default)]
+    pub outgoing: Card,
+    pub incoming: Card,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct ProposerState {
+    pub selector: SelectorState,
+    pub slots: SlotProposal,
+    pub accepted: Vec<u32>,
+    pub rejected: Vec<u32>,
+}
+
+pub struct Proposer {
+    setlist: SetList,
+    cards: Box<dyn CardSelector>,
+    slots: SlotProposal,
+    accepted: Vec<u32>,
+    rejected: Vec<u32>,
+}
+
+//Softmax temperature on the win rate when played of the cards of the deck
+const CONTRIBUTION_TEMPERATURE: f64 = 0.05;
+
+//Cards drawn at most by the selector before drawing among the cards that can enter the deck
+const MAX_CARD_DRAWS: usize = 100;
+
+impl Proposer {
+    pub fn new(setlist: SetList, cards: CardProposal, slots: SlotProposal) -> Proposer {
+        let size = setlist.len();
+        Proposer::restore(
+            setlist,
+            ProposerState {
+                selector: SelectorState::initial(cards, size),
+                slots,
+                accepted: vec![0; size],
+                rejected: vec![0; size],
+            },
+        )
+    }
+
+    pub fn restore(setlist: SetList, state: ProposerState) -> Proposer {
+        Proposer {
+            cards: state.selector.build(&setlist),
+            setlist,
+            slots: state.slots,
+            accepted: state.accepted,
+            rejected: state.rejected,
+        }
+    }
+
+    pub fn state(&self) -> ProposerState {
+        ProposerState {
+            selector: self.cards.state(),
+            slots: self.slots,
+            accepted: self.accepted.clone(),
+            rejected: self.rejected.clone(),
+        }
+    }
+
+    //The contribution of the cards is only needed by SlotProposal::Contribution
+    pub fn needs_card_stats(&self) -> bool {
+        self.slots == SlotProposal::Contribution
+    }
+
+    //Cards already MAX_COPIES times in the deck are drawn again, after MAX_CARD_DRAWS the card is
+    //drawn by weight among the cards that can still enter the deck. The slot replaced never holds
+    //the incoming card. None when no card can enter the deck.
+    pub fn propose(
+        &mut self,
+        deck: &Deck,
+        card_stats: Option<&CardStats>,
+        rng: &mut dyn RngCore,
+    ) -> Option<Proposal> {
+        let can_enter =
+            |card: &Card| deck.iter().filter(|other| *other == card).count() < MAX_COPIES;
+        let mut card_index = self.cards.select(rng);
+        for _ in 1..MAX_CARD_DRAWS {
+            match card_index {
+                Some(index) if !can_enter(&self.setlist[index]) => {
+                    card_index = self.cards.select(rng)
+                }
+                _ => break,
+            }
+        }
+        let card_index = match card_index {
+            Some(index) if can_enter(&self.setlist[index]) => index,
+            _ => self.draw_card_entering(deck, rng)?,
+        };
+        let incoming = self.setlist[card_index];
+
+        let slot = match (self.slots, card_stats) {
+            (SlotProposal::Contribution, Some(card_stats)) => {
+                contribution_slot(deck, card_stats, &incoming, rng)
+            }
+            _ => {
+                let slots: Vec<usize> = (0..deck.len())
+                    .filter(|slot| deck[*slot] != incoming)
+                    .collect();
+                slots[rng.gen_range(0..slots.len())]
+            }
+        };
+
+        Some(Proposal {
+            slot,
+            card_index,
+            outgoing: deck[slot],
+            incoming,
+        })
+    }
+
+    //Draws in proportion to the learned weights among the cards the deck holds less than
+    //MAX_COPIES times, None when none of them has a positive weight
+    fn draw_card_entering(&self, deck: &Deck, rng: &mut dyn RngCore) -> Option<usize> {
+        let weights = self.cards.weights();
+        let entering: Vec<usize> = (0..self.setlist.len())
+            .filter(|index| {
+                weights[*index] > 0.0
+                    && deck
+                        .iter()
+                        .filter(|card| **card == self.setlist[*index])
+                        .count()
+                        < MAX_COPIES
+            })
+            .collect();
+        if entering.is_empty() {
+            return None;
+        }
+
+        let mut target =
+            rng.gen::<f64>() * entering.iter().map(|index| weights[*index]).sum::<f64>();
+        for index in &entering {
+            if target < weights[*index] {
+                return Some(*index);
+            }
+            target -= weights[*index];
+        }
+        entering.last().copied()
+    }
+
+    pub fn feedback(&mut self, proposal: &Proposal, accepted: bool) {
+        if accepted {
+            self.accepted[proposal.card_index] += 1;
+        } else {
+            self.rejected[proposal.card_index] += 1;
+        }
+        self.cards.update(proposal.card_index, accepted);
+    }
+
+    //Setlist cards with their learned weight, highest first
+    pub fn card_weights(&self) -> Vec<(Card, f64)> {
+        let mut weights: Vec<_> = self
+            .setlist
+            .iter()
+            .copied()
+            .zip(self.cards.weights())
+            .collect();
+        weights.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        weights
+    }
+
+    pub fn export_weights(&self, names: &CardNames, file: &mut File) {
+        file.write_all(b"Id,Name,Cost,Weight,Accepted,Rejected\n")
+            .unwrap();
+
+        let weights = self.cards.weights();
+        let mut order: Vec<usize> = (0..self.setlist.len()).collect();
+        order.sort_by(|a, b| weights[*b].total_cmp(&weights[*a]));
+
+        for index in order {
+            let card = &self.setlist[index];
+            file.write_all(
+                format!(
+                    "{},{},{},{:.6},{},{}\n",
+                    card.get_id(),
+                    names.label(card),
+                    card.get_cost(),
+                    weights[index],
+                    self.accepted[index],
+                    self.rejected[index],
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+        }
+    }
+}
+
+//Cards that win less when played are replaced more often, cards never played count as the worst.
+//Slots holding the incoming card are never replaced.
+fn contribution_slot(
+    deck: &Deck,
+    card_stats: &CardStats,
+    incoming: &Card,
+    rng: &mut dyn RngCore,
+) -> usize {
+    let win_rates: Vec<f64> = deck
+        .iter()
+        .map(|card| card_stats.get(card).win_rate_when_played())
+        .collect();
+    let min = win_rates.iter().copied().fold(f64::INFINITY, f64::min);
+    let weights: Vec<f64> = deck
+        .iter()
+        .zip(&win_rates)
+        .map(|(card, win_rate)| {
+            if card == incoming {
+                0.0
+            } else {
+                (-(win_rate - min) / CONTRIBUTION_TEMPERATURE).exp()
+            }
+        })
+        .collect();
+
+    let mut target = rng.gen::<f64>() * weights.iter().sum::<f64>();
+    for (slot, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            return slot;
+        }
+        target -= weight;
+    }
+    (0..deck.len())
+        .rev()
+        .find(|slot| weights[*slot] > 0.0)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::game::deck::DeckTrait;
+
+    fn setlist(size: u32) -> SetList {
+        (0..size)
+            .map(|i| Card::new(i + 1, 1, false, false, false, false))
+            .collect()
+    }
+
+    //A pool of 10 cards fills the deck with MAX_COPIES of each, no card can enter it
+    #[test]
+    fn saturated_deck_has_no_proposal() {
+        let mut rng = ChaCha8Rng::seed_from_u64(37);
+        let setlist = setlist(10);
+        let deck = Deck::new(&setlist, &mut rng);
+        for cards in [
+            CardProposal::Additive,
+            CardProposal::Exp3,
+            CardProposal::Thompson,
+        ] {
+            let mut proposer = Proposer::new(setlist.clone(), cards, SlotProposal::Uniform);
+            assert!(proposer.propose(&deck, None, &mut rng).is_none());
+        }
+    }
+
+    //Nine cards of the pool are at MAX_COPIES: proposals never add a copy past the limit and never
+    //replace a card by itself
+    #[test]
+    fn proposals_respect_the_copy_limit() {
+        let mut rng = ChaCha8Rng::seed_from_u64(37);
+        let setlist = setlist(11);
+        let mut deck = Deck::new(&setlist[..10].into(), &mut rng);
+        deck[0] = setlist[10];
+        for slots in [SlotProposal::Uniform, SlotProposal::Contribution] {
+            let mut proposer = Proposer::new(setlist.clone(), CardProposal::Additive, slots);
+            let card_stats = CardStats::default();
+            for _ in 0..200 {
+                let proposal = proposer
+                    .propose(&deck, Some(&card_stats), &mut rng)
+                    .unwrap();
+                assert_ne!(deck[proposal.slot], proposal.incoming);
+                assert!(
+                    deck.iter()
+                        .filter(|card| **card == proposal.incoming)
+                        .count()
+                        < MAX_COPIES
+                );
+                proposer.feedback(&proposal, false);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_weights_have_no_proposal() {
+        let mut rng = ChaCha8Rng::seed_from_u64(37);
+        let setlist = setlist(40);
+        let deck = Deck::new(&setlist, &mut rng);
+        let mut proposer = Proposer::restore(
+            setlist.clone(),
+            ProposerState {
+                selector: SelectorState::Additive {
+                    weights: vec![0; 40],
+                    step: 1,
+                },
+                slots: SlotProposal::Uniform,
+                accepted: vec![0; 40],
+                rejected: vec![0; 40],
+            },
+        );
+        assert!(proposer.propose(&deck, None, &mut rng).is_none());
+    }
+}
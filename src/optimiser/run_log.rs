@@ -0,0 +1,277 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    coevolution::{PhaseResult, RunSummary},
+    hill_climb::Swap,
+};
+use crate::game::{card::Card, deck::Deck};
+
+//Output of an optimisation run, JSON Lines: one record per line, told apart by "Type".
+//Field names are PascalCase, scores are win rates between 0 and 1, cards are referred to by Id.
+//
+//Run    written when the run starts and again on every resume
+//       Seed, Games (per seat and opponent at each evaluation), Iterations (per phase), Rounds,
+//       Resumed, Arguments (command line), Setlist (every card with its Id and stats)
+//Step   one per iteration
+//       Round, Side (0 deck A, 1 deck B), Iteration (within the phase), Score (the deck
+//       evaluated), WinRate (the deck kept), AvgTurn, Accepted, Games (played by the step),
+//       Deck (Ids of the deck kept), Swaps (the swap evaluated, every swap of the neighbourhood best
+//       first in steepest ascent mode, empty when the step evaluated a new deck)
+//       Swap: Slot, Outgoing, Incoming, ScoreBefore, ScoreAfter, Accepted
+//Phase  when a phase ends
+//       Round, Side, StartWinRate, EndWinRate, Accepted, Iterations, Stop
+//End    when the run ends
+//       Seed, Stop, Steps, Games, Elapsed (seconds), Phases, DeckA, DeckB
+//
+//Stop is one of Iterations, NoImprovement, Plateau, TimeBudget or GameBudget.
+//A resumed run starts again from its last checkpoint: the log is cut back to the checkpoint, then a
+//Run record with Resumed set is written and the run goes on. Logs of runs resumed before the log
+//was cut hold the steps after the checkpoint twice, the export keeps the ones written last.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "Type")]
+pub enum Record {
+    Run(RunRecord),
+    Step(StepRecord),
+    Phase(PhaseResult),
+    End(EndRecord),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct SetlistCard {
+    pub id: String,
+    #[serde(flatten)]
+    pub card: Card,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct RunRecord {
+    pub seed: u64,
+    pub games: u32,
+    pub iterations: usize,
+    pub rounds: usize,
+    pub resumed: bool,
+    pub arguments: Vec<String>,
+    pub setlist: Vec<SetlistCard>,
+}
+
+impl RunRecord {
+    pub fn setlist(cards: &[Card]) -> Vec<SetlistCard> {
+        cards
+            .iter()
+            .map(|card| SetlistCard {
+                id: card.get_id(),
+                card: *card,
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct SwapRecord {
+    pub slot: usize,
+    pub outgoing: String,
+    pub incoming: String,
+    pub score_before: f64,
+    pub score_after: f64,
+    #[serde(default)]
+    pub accepted: bool,
+}
+
+impl From<&Swap> for SwapRecord {
+    fn from(swap: &Swap) -> SwapRecord {
+        SwapRecord {
+            slot: swap.slot,
+            outgoing: swap.outgoing.get_id(),
+            incoming: swap.incoming.get_id(),
+            score_before: swap.score_before,
+            score_after: swap.score_after,
+            accepted: swap.accepted,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct StepRecord {
+    pub round: usize,
+    pub side: usize,
+    pub iteration: usize,
+    pub score: f64,
+    pub win_rate: f64,
+    pub avg_turn: f32,
+    pub accepted: bool,
+    pub games: u32,
+    pub deck: Vec<String>,
+    #[serde(default)]
+    pub swaps: Vec<SwapRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct EndRecord {
+    #[serde(flatten)]
+    pub summary: RunSummary,
+    pub deck_a: Vec<String>,
+    pub deck_b: Vec<String>,
+}
+
+pub fn deck_ids(deck: &Deck) -> Vec<String> {
+    deck.iter().map(Card::get_id).collect()
+}
+
+pub struct RunLog {
+    file: File,
+    length: u64,
+}
+
+impl RunLog {
+    //A resumed run continues its log from the length it had at the checkpoint, the records written
+    //after the checkpoint are written again by the resumed run
+    pub fn open(path: &str, resume_at: Option<u64>) -> RunLog {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(resume_at.is_none())
+            .open(path)
+            .unwrap();
+        let length = resume_at.unwrap_or(0);
+        if file.metadata().unwrap().len() < length {
+            panic!("{path} is shorter than when the checkpoint was saved, it is not the log of this run");
+        }
+        file.set_len(length).unwrap();
+        file.seek(SeekFrom::End(0)).unwrap();
+        RunLog { file, length }
+    }
+
+    //Bytes written so far
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    pub fn write(&mut self, record: &Record) {
+        let line = format!("{}\n", serde_json::to_string(record).unwrap());
+        self.file.write_all(line.as_bytes()).unwrap();
+        self.length += line.len() as u64;
+    }
+
+    pub fn load(path: &str) -> Vec<Record> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .unwrap_or_else(|error| panic!("{path}:{}: {error}", i + 1))
+            })
+            .collect()
+    }
+}
+
+//One row per swap evaluated by a step, or per step when it evaluated a new deck, with the deck kept
+//summarised for spreadsheets: average stats, number of cards with each keyword and the Ids
+//separated by spaces
+pub fn export_steps_csv(records: &[Record], file: &mut File) {
+    file.write_all(
+        b"Round,Side,Iteration,Score,WinRate,AvgTurn,Accepted,Games,AvgCost,AvgAttack,AvgDefense,\
+Taunt,Distortion,Trample,FirstStrike,Slot,Outgoing,Incoming,ScoreBefore,ScoreAfter,SwapAccepted,\
+Deck\n",
+    )
+    .unwrap();
+
+    let mut cards: HashMap<String, Card> = HashMap::new();
+    let mut steps: Vec<&StepRecord> = vec![];
+    let mut resumed = false;
+    for record in records {
+        match record {
+            Record::Run(run) => {
+                for card in &run.setlist {
+                    cards.insert(card.id.clone(), card.card);
+                }
+                resumed = run.resumed;
+            }
+            Record::Step(step) => {
+                //The first step of a resumed run replaces the steps written after its checkpoint
+                if resumed {
+                    let position = |step: &StepRecord| (step.round, step.side, step.iteration);
+                    steps.retain(|&kept| position(kept) < position(step));
+                    resumed = false;
+                }
+                steps.push(step);
+            }
+            _ => {}
+        }
+    }
+
+    for step in steps {
+        let deck: Vec<Card> = step
+            .deck
+            .iter()
+            .map(|id| {
+                *cards
+                    .get(id)
+                    .unwrap_or_else(|| panic!("Card {id} is not in the setlist of the run"))
+            })
+            .collect();
+        let average = |value: fn(&Card) -> u32| {
+            deck.iter().map(|card| value(card) as f64).sum::<f64>() / deck.len() as f64
+        };
+        let count = |value: fn(&Card) -> bool| deck.iter().filter(|card| value(card)).count();
+        let swaps: Vec<String> = step
+            .swaps
+            .iter()
+            .map(|swap| {
+                format!(
+                    "{},{},{},{:.4},{:.4},{}",
+                    swap.slot,
+                    swap.outgoing,
+                    swap.incoming,
+                    swap.score_before,
+                    swap.score_after,
+                    swap.accepted as u32
+                )
+            })
+            .collect();
+        let swaps = if swaps.is_empty() {
+            vec![",,,,,".to_owned()]
+        } else {
+            swaps
+        };
+
+        for swap in swaps {
+            file.write_all(
+                format!(
+                    "{},{},{},{:.4},{:.4},{},{},{},{:.3},{:.3},{:.3},{},{},{},{},{},{}\n",
+                    step.round,
+                    step.side,
+                    step.iteration,
+                    step.score,
+                    step.win_rate,
+                    step.avg_turn,
+                    step.accepted as u32,
+                    step.games,
+                    average(Card::get_cost),
+                    average(Card::get_attack),
+                    average(Card::get_defense),
+                    count(Card::get_taunt),
+                    count(Card::get_distortion),
+                    count(Card::get_trample),
+                    count(Card::get_first_strike),
+                    swap,
+                    step.deck.join(" ")
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use super::proposal::Proposal;
+use crate::game::deck::Deck;
+
+//What the hill climber does once it has not improved on its best deck for a while
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Restart {
+    #[default]
+    None,
+    //Starts again from a new random deck
+    Random,
+    //Iterated local search: starts again from the best deck after a few random swaps
+    Iterated,
+}
+
+impl Restart {
+    pub fn from_name(name: &str) -> Option<Restart> {
+        match name.to_lowercase().as_str() {
+            "none" => Some(Restart::None),
+            "random" => Some(Restart::Random),
+            "ils" | "iterated" => Some(Restart::Iterated),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "PascalCase")]
+pub struct SearchConfig {
+    //Iterations during which a tried (slot, card) swap is not proposed again, 0 disables the list
+    pub tabu_tenure: usize,
+    pub restart: Restart,
+    //Iterations without a better deck before a restart
+    pub restart_after: usize,
+    //Random swaps applied to the best deck by an iterated restart
+    pub kick: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> SearchConfig {
+        SearchConfig {
+            tabu_tenure: 0,
+            restart: Restart::None,
+            restart_after: 100,
+            kick: 3,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "PascalCase")]
+pub struct TabuSwap {
+    pub slot: usize,
+    pub card_index: usize,
+    pub expires: usize,
+}
+
+//Memory of the hill climber. With the default configuration it draws nothing from the random
+//stream, so that runs stay identical to the plain hill climber on the same seed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Search {
+    pub config: SearchConfig,
+    pub tabu: Vec<TabuSwap>,
+    //Best deck evaluated since the start of the phase, restarts move away from it
+    pub best: Option<(Deck, f64)>,
+    pub stale: usize,
+    pub restarts: usize,
+    pub tabu_hits: usize,
+}
+
+//Proposals drawn again at most when they are tabu, the last one is kept otherwise
+const MAX_TABU_DRAWS: usize = 100;
+
+impl Search {
+    //Forgets everything but the configuration and the counters
+    pub fn rebase(&mut self) {
+        self.tabu.clear();
+        self.best = None;
+        self.stale = 0;
+    }
+
+    //Draws proposals until one is not tabu, then makes it tabu for the tenure. None when there is
+    //no proposal to draw.
+    pub fn choose(
+        &mut self,
+        iteration: usize,
+        mut draw: impl FnMut() -> Option<Proposal>,
+    ) -> Option<Proposal> {
+        self.tabu.retain(|swap| swap.expires > iteration);
+        let mut proposal = draw()?;
+        for _ in 0..MAX_TABU_DRAWS {
+            let is_tabu = self
+                .tabu
+                .iter()
+                .any(|swap| swap.slot == proposal.slot && swap.card_index == proposal.card_index);
+            if !is_tabu {
+                break;
+            }
+            self.tabu_hits += 1;
+            proposal = draw()?;
+        }
+
+        if self.config.tabu_tenure > 0 {
+            self.tabu.push(TabuSwap {
+                slot: proposal.slot,
+                card_index: proposal.card_index,
+                expires: iteration + self.config.tabu_tenure,
+            });
+        }
+        Some(proposal)
+    }
+
+    //Records an evaluated deck, true when it is time to restart
+    pub fn observe(&mut self, deck: &Deck, score: f64) -> bool {
+        if self.best.is_none_or(|(_, best_score)| best_score < score) {
+            self.best = Some((*deck, score));
+            self.stale = 0;
+        } else {
+            self.stale += 1;
+        }
+
+        if self.config.restart == Restart::None || self.stale < self.config.restart_after {
+            return false;
+        }
+        self.stale = 0;
+        self.restarts += 1;
+        true
+    }
+}
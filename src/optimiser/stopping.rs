@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use super::hill_climb::Step;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    //Every iteration of every phase has run
+    #[default]
+    Iterations,
+    //The best win rate of the phase has not risen by min_delta for patience steps
+    NoImprovement,
+    //The average win rate of the deck kept over the last plateau steps is not min_delta above the
+    //steps before
+    Plateau,
+    TimeBudget,
+    GameBudget,
+}
+
+//Criteria left to None are not checked. Improvement and plateau end the phase being optimised,
+//budgets end the whole run.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct Stopping {
+    pub patience: Option<usize>,
+    pub plateau: Option<usize>,
+    pub min_delta: Option<f64>,
+    //Seconds
+    pub max_time: Option<f64>,
+    pub max_games: Option<u64>,
+}
+
+impl Stopping {
+    //Changes smaller than the standard error of a win rate measured on 2 n games are noise
+    pub fn noise(n_games: u32) -> f64 {
+        0.5 / (2.0 * n_games as f64).sqrt()
+    }
+
+    pub fn phase_stop(&self, phase: &[Step]) -> Option<StopReason> {
+        let min_delta = self.min_delta.unwrap_or(0.0);
+        //Score of the deck kept after each step, rejected candidates leave it unchanged
+        let kept: Vec<f64> = phase
+            .iter()
+            .scan(0.0, |kept, step| {
+                if step.accepted {
+                    *kept = step.score;
+                }
+                Some(*kept)
+            })
+            .collect();
+
+        if let Some(patience) = self.patience {
+            let mut record = f64::NEG_INFINITY;
+            let mut last_improvement = 0;
+            for (i, &score) in kept.iter().enumerate() {
+                if score > record + min_delta {
+                    record = score;
+                    last_improvement = i;
+                }
+            }
+            if phase.len() - last_improvement > patience {
+                return Some(StopReason::NoImprovement);
+            }
+        }
+
+        if let Some(window) = self
+            .plateau
+            .filter(|&window| window > 0 && phase.len() >= 2 * window)
+        {
+            let mean = |scores: &[f64]| scores.iter().sum::<f64>() / scores.len() as f64;
+            let recent = mean(&kept[kept.len() - window..]);
+            let before = mean(&kept[kept.len() - 2 * window..kept.len() - window]);
+            if recent - before < min_delta {
+                return Some(StopReason::Plateau);
+            }
+        }
+        None
+    }
+
+    pub fn budget_stop(&self, elapsed: f64, games: u64) -> Option<StopReason> {
+        if self.max_time.is_some_and(|max_time| elapsed >= max_time) {
+            Some(StopReason::TimeBudget)
+        } else if self.max_games.is_some_and(|max_games| games >= max_games) {
+            Some(StopReason::GameBudget)
+        } else {
+            None
+        }
+    }
+}
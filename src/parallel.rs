@@ -0,0 +1,28 @@
+use std::thread;
+
+//Maps every item with its index on one thread per core, the results keep the order of the items
+pub fn parallel_map<T: Sync, U: Send>(items: &[T], f: impl Fn(usize, &T) -> U + Sync) -> Vec<U> {
+    let n_threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = items.len().div_ceil(n_threads).max(1);
+    let f = &f;
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .enumerate()
+            .map(|(chunk, items)| {
+                scope.spawn(move || {
+                    items
+                        .iter()
+                        .enumerate()
+                        .map(|(index, item)| f(chunk * chunk_size + index, item))
+                        .collect::<Vec<U>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}